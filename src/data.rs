@@ -1,29 +1,96 @@
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::fmt;
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 use mint::{Point2, Vector2};
-use quick_xml::de::from_reader;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-use crate::deserial::TMXError;
+use crate::diagnostic::{self, Resources, Warning};
+use crate::error::TMXError;
+use crate::json::{self, JsonMap, JsonTileSet, TMJError};
+use crate::project::Project;
+use crate::resource::{FileSystem, ResourceReader};
+use crate::serial;
 
 /// Identifiant global représentant sur la map l'absence de tuile.
 pub const EMPTY_TILE: u16 = 0;
 
+/// Bits de poids fort d'un identifiant global lu dans un fichier, indiquant la
+/// symétrie ou la rotation de la tuile.
+const FLIP_FLAGS: u32 = 0xF000_0000;
+
+/// Convertit un identifiant global lu dans un fichier en identifiant de tuile,
+/// en ignorant ses bits de symétrie et de rotation.
+///
+/// Retourne `None` si l'identifiant reste trop grand une fois ces bits retirés.
+pub(crate) fn tile_gid(value: u32) -> Option<u16> {
+    u16::try_from(value & !FLIP_FLAGS).ok()
+}
+
 /// Contient les données associées à une image.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Image {
     /// Chemin d'accès de l'image.
+    ///
+    /// Le chemin est vide lorsque l'image est intégrée au fichier.
     pub source: String,
+    /// Chemin d'accès de l'image résolu par rapport au fichier la référençant,
+    /// lorsque celui-ci a été chargé depuis un chemin d'accès.
+    pub path: Option<PathBuf>,
     /// Taille de l'image en pixels.
     pub size: Vector2<u16>,
+    /// Couleur de l'image à considérer comme transparente.
+    pub trans: Option<Color>,
+    /// Format de l'image (`png`, `gif`, ...), utilisé pour les images intégrées.
+    pub format: Option<String>,
+    /// Contenu de l'image lorsque celle-ci est intégrée au fichier.
+    pub data: Option<Vec<u8>>,
 }
 
 impl Image {
     /// Crée une nouvelle image.
     pub fn new(source: impl Into<String>, size: Vector2<u16>) -> Self {
-        Self { source: source.into(), size }
+        Self {
+            source: source.into(),
+            path: None,
+            size,
+            trans: None,
+            format: None,
+            data: None,
+        }
+    }
+
+    /// Indique si le contenu de l'image est intégré au fichier.
+    #[inline]
+    pub fn is_embedded(&self) -> bool {
+        self.data.is_some()
+    }
+
+    /// Renvoie le contenu de l'image, lu à l'aide de la source de fichiers passée
+    /// en paramètre lorsqu'il n'est pas intégré au fichier.
+    ///
+    /// Le fichier lu est celui désigné par [`Image::path`] ou, à défaut, par
+    /// [`Image::source`].
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si le fichier de l'image ne peut être
+    /// lu.
+    pub fn read<R: ResourceReader>(&self, reader: R) -> io::Result<Vec<u8>> {
+        if let Some(data) = &self.data {
+            return Ok(data.clone());
+        }
+
+        let path = self.path.as_deref().unwrap_or_else(|| Path::new(&self.source));
+        let mut data = Vec::new();
+        reader.open(path)?.read_to_end(&mut data)?;
+
+        Ok(data)
     }
 }
 
@@ -108,29 +175,84 @@ impl TilesOrigin {
 pub struct TileSet {
     /// Identifiant global à partir duquel la tuile appartient à ce jeu.
     pub firstgid: u16,
+    /// Chemin d'accès du fichier décrivant le jeu, lorsque celui-ci est externe à
+    /// la map.
+    pub source: Option<String>,
+    /// Chemin d'accès résolu du fichier décrivant le jeu, lorsque celui-ci a été
+    /// chargé avec la map.
+    pub path: Option<PathBuf>,
     /// Taille en pixel des tuiles du jeu.
     pub size: Vector2<u16>,
     /// Nombre de tuiles que possède le jeu.
     pub count: u16,
     /// Nombre de colonnes que possède le jeu.
     pub columns: u16,
+    /// Espacement en pixels entre les tuiles de l'image du jeu.
+    pub spacing: u16,
+    /// Marge en pixels autour des tuiles de l'image du jeu.
+    pub margin: u16,
     /// Nom du jeu de tuile.
     pub name: String,
     /// Origine des tuiles du jeu.
     pub origin: Option<TilesOrigin>,
+    /// Propriétés personnalisées du jeu.
+    pub properties: Properties,
+    /// Propriétés personnalisées de chaque tuile du jeu, indexées par leur
+    /// identifiant local.
+    pub tiles_properties: BTreeMap<u16, Properties>,
 }
 
 impl TileSet {
-    /// Renvoie le nombre de lignes que possède le jeu.
+    /// Instancie un nouveau jeu de tuiles depuis les données d'un fichier `.tsj`.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si les données passées en paramètre ne
+    /// sont pas au format JSON ou ne décrivent pas un jeu de tuiles.
+    pub fn load_tsj<D: AsRef<[u8]>>(data: D) -> Result<Self, TMJError> {
+        TileSet::try_from(serde_json::from_slice::<JsonTileSet>(data.as_ref())?)
+    }
+
+    /// Écrit le jeu de tuiles au format `.tsj`.
+    ///
+    /// Le `firstgid` du jeu n'est pas écrit, celui-ci dépendant de la map qui le
+    /// référence.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si l'écriture échoue.
+    #[inline]
+    pub fn write_tsj<W: Write>(&self, writer: W) -> Result<(), TMJError> {
+        json::write_tileset(self, writer)
+    }
+
+    /// Renvoie le nombre de lignes que possède le jeu, ou 0 si celui-ci ne
+    /// possède aucune colonne.
     #[inline]
     pub fn rows(&self) -> u16 {
-        self.count / self.columns
+        self.count.checked_div(self.columns).unwrap_or(0)
     }
 
     /// Renvoie le dernier identifiant global appartenant au jeu de tuiles.
+    ///
+    /// Celui-ci est limité à `u16::MAX` lorsque le jeu dépasse les identifiants
+    /// représentables.
     #[inline]
     pub fn last_gid(&self) -> u16 {
-        self.firstgid + self.last_id()
+        self.firstgid.saturating_add(self.last_id())
+    }
+
+    /// Renvoie la plage des identifiants globaux appartenant au jeu de tuiles.
+    #[inline]
+    pub fn gids(&self) -> RangeInclusive<u16> {
+        self.firstgid..=self.last_gid()
+    }
+
+    /// Renvoie les propriétés personnalisées de la tuile dont l'identifiant local
+    /// est passé en paramètre.
+    #[inline]
+    pub fn tile_properties(&self, id: u16) -> Option<&Properties> {
+        self.tiles_properties.get(&id)
     }
 
     /// Renvoie le dernier identifiant local de tuile (celui avec la plus haute
@@ -148,11 +270,17 @@ impl Default for TileSet {
     fn default() -> Self {
         Self {
             firstgid: u16::MAX,
+            source: None,
+            path: None,
             size: Vector2 { x: 0, y: 0 },
             count: 0,
             columns: 0,
+            spacing: 0,
+            margin: 0,
             name: String::from("unnamed"),
             origin: None,
+            properties: Properties::new(),
+            tiles_properties: BTreeMap::new(),
         }
     }
 }
@@ -162,7 +290,7 @@ impl Default for TileSet {
 /// Contrairement aux tuiles, les objets possèdent l'avantages de ne pas avoir à
 /// être alignés sur la grille, et peuvent donc servir à représenter diverses
 /// informations.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Object {
     /// Identifiant unique de l'objet.
     pub id: u16,
@@ -174,6 +302,13 @@ pub struct Object {
     pub coords: Point2<u16>,
     /// Taille de l'objet en pixels.
     pub size: Vector2<u16>,
+    /// Propriétés personnalisées de l'objet.
+    pub properties: Properties,
+    /// Chemin d'accès du modèle dont l'objet reprend les valeurs.
+    ///
+    /// Lorsque la map est chargée depuis un chemin d'accès, les valeurs non
+    /// renseignées de l'objet sont complétées par celles du modèle.
+    pub template: Option<String>,
 }
 
 impl Object {
@@ -192,6 +327,8 @@ impl Default for Object {
             gid: 0,
             coords: Point2 { x: 0, y: 0 },
             size: Vector2 { x: 0, y: 0 },
+            properties: Properties::new(),
+            template: None,
         }
     }
 }
@@ -200,28 +337,106 @@ impl Default for Object {
 ///
 /// Tout comme les tuiles, les objets sont rassemblés par calques, ici appelés
 /// groupes.
-#[derive(Clone, Debug, Eq, PartialEq, Deserialize)]
-#[serde(rename = "objectgroup")]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct ObjectGroup {
     /// Identifiant unique du calque.
     pub id: u16,
     /// Nom du groupe d'objet.
     pub name: String,
     /// Liste des objets appartenant au groupe.
-    #[serde(rename = "object")]
     pub objects: Vec<Object>,
+    /// Propriétés personnalisées du groupe.
+    pub properties: Properties,
 }
 
-impl Default for ObjectGroup {
-    fn default() -> Self {
-        Self {
-            id: 0,
-            name: String::from(""),
-            objects: Vec::new(),
+/// Liste des propriétés personnalisées d'un élément, indexées par leur nom.
+pub type Properties = BTreeMap<String, PropertyValue>;
+
+/// Représente la valeur d'une propriété personnalisée.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyValue {
+    /// Valeur booléenne.
+    Bool(bool),
+    /// Couleur.
+    Color(Color),
+    /// Nombre à virgule flottante.
+    Float(f64),
+    /// Chemin d'accès vers un fichier.
+    File(String),
+    /// Nombre entier.
+    Int(i32),
+    /// Identifiant d'un objet de la map.
+    ///
+    /// Lorsqu'aucun objet n'est référencé, l'identifiant vaut 0.
+    Object(u16),
+    /// Chaîne de caractères.
+    String(String),
+    /// Instance d'une classe personnalisée.
+    Class {
+        /// Nom de la classe.
+        property_type: String,
+        /// Valeur des membres de la classe.
+        properties: Properties,
+    },
+    /// Valeur d'une énumération personnalisée.
+    Enum {
+        /// Nom de l'énumération.
+        property_type: String,
+        /// Valeur stockée de l'énumération.
+        value: EnumValue,
+    },
+}
+
+impl PropertyValue {
+    /// Renvoie le nom du type de la propriété tel qu'utilisé par Tiled.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::Bool(_) => "bool",
+            Self::Color(_) => "color",
+            Self::Float(_) => "float",
+            Self::File(_) => "file",
+            Self::Int(_) => "int",
+            Self::Object(_) => "object",
+            Self::String(_) => "string",
+            Self::Class { .. } => "class",
+            Self::Enum { value: EnumValue::String(_), .. } => "string",
+            Self::Enum { value: EnumValue::Int(_), .. } => "int",
+        }
+    }
+
+    /// Associe la valeur au type personnalisé passé en paramètre.
+    ///
+    /// Les chaînes de caractères et les entiers associés à un type deviennent la
+    /// valeur d'une énumération, tandis que les classes prennent son nom.
+    pub(crate) fn with_custom_type(self, custom_type: Option<String>) -> Self {
+        let custom_type = custom_type.filter(|custom_type| !custom_type.is_empty());
+
+        match (self, custom_type) {
+            (Self::String(s), Some(property_type)) => {
+                Self::Enum { property_type, value: EnumValue::String(s) }
+            }
+            (Self::Int(i), Some(property_type)) => {
+                Self::Enum { property_type, value: EnumValue::Int(i) }
+            }
+            (Self::Class { properties, .. }, property_type) => Self::Class {
+                property_type: property_type.unwrap_or_default(),
+                properties,
+            },
+            (value, _) => value,
         }
     }
 }
 
+/// Représente la valeur stockée d'une énumération personnalisée.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EnumValue {
+    /// Nom de la valeur, ou liste de noms séparés par des virgules pour une
+    /// énumération de drapeaux.
+    String(String),
+    /// Indice de la valeur, ou champ de bits pour une énumération de drapeaux.
+    Int(i32),
+}
+
 /// Représente les erreurs possibles lors de la conversion d'une chaîne de
 /// caractère.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -250,7 +465,7 @@ impl fmt::Display for ParsingError {
 }
 
 /// Représente les différentes orientations possibles pour une grille.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum Orientation {
     /// Il s'agit d'une grille orthogonale.
@@ -278,8 +493,19 @@ impl FromStr for Orientation {
     }
 }
 
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Orthogonal => "orthogonal",
+            Self::Isometric => "isometric",
+            Self::Staggered => "staggered",
+            Self::Hexagonal => "hexagonal",
+        }.fmt(f)
+    }
+}
+
 /// Représente l'axe de décalage d'une map.
-#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub enum StaggerAxis {
     /// La map possède un décalage sur l'axe x.
     #[serde(rename = "x")]
@@ -305,13 +531,260 @@ impl FromStr for StaggerAxis {
     }
 }
 
+impl fmt::Display for StaggerAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::XAxis => "x",
+            Self::YAxis => "y",
+            Self::None => "",
+        }.fmt(f)
+    }
+}
+
+/// Représente les lignes ou colonnes décalées d'une map décalée ou hexagonale,
+/// selon son axe de décalage.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaggerIndex {
+    /// Les lignes ou colonnes impaires sont décalées.
+    #[default]
+    Odd,
+    /// Les lignes ou colonnes paires sont décalées.
+    Even,
+}
+
+impl FromStr for StaggerIndex {
+    type Err = ParsingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "odd" => Ok(Self::Odd),
+            "even" => Ok(Self::Even),
+            "" => Err(ParsingError::EmptyString),
+            _ => Err(ParsingError::InvalidString(String::from(s))),
+        }
+    }
+}
+
+impl fmt::Display for StaggerIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Odd => "odd",
+            Self::Even => "even",
+        }.fmt(f)
+    }
+}
+
+/// Représente l'encodage utilisé pour le contenu des calques de tuiles.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Encoding {
+    /// Les identifiants globaux sont séparés par des virgules.
+    #[default]
+    Csv,
+    /// Les identifiants globaux sont stockés sur 4 octets, éventuellement
+    /// compressés, puis encodés en base64.
+    Base64(Option<Compression>),
+}
+
+/// Représente les algorithmes de compression utilisables pour le contenu des
+/// calques de tuiles encodé en base64.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Compression {
+    /// Compression au format zlib.
+    Zlib,
+    /// Compression au format gzip.
+    Gzip,
+    /// Compression au format zstandard, disponible avec la fonctionnalité `zstd`.
+    Zstd,
+}
+
+impl FromStr for Compression {
+    type Err = ParsingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "zlib" => Ok(Self::Zlib),
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            "" => Err(ParsingError::EmptyString),
+            _ => Err(ParsingError::InvalidString(String::from(s))),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Zlib => "zlib",
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }.fmt(f)
+    }
+}
+
+/// Représente une couleur, telle que décrite par Tiled au format `#AARRGGBB`.
+///
+/// Le caractère `#` ainsi que la composante alpha sont facultatifs : une couleur
+/// au format `RRGGBB` est considérée comme totalement opaque.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Color {
+    /// Composante rouge de la couleur.
+    pub red: u8,
+    /// Composante verte de la couleur.
+    pub green: u8,
+    /// Composante bleue de la couleur.
+    pub blue: u8,
+    /// Composante alpha de la couleur.
+    pub alpha: u8,
+}
+
+impl Color {
+    /// Crée une nouvelle couleur totalement opaque.
+    pub const fn rgb(red: u8, green: u8, blue: u8) -> Self {
+        Self::rgba(red, green, blue, u8::MAX)
+    }
+
+    /// Crée une nouvelle couleur.
+    pub const fn rgba(red: u8, green: u8, blue: u8, alpha: u8) -> Self {
+        Self { red, green, blue, alpha }
+    }
+
+    /// Renvoie les composantes de la couleur normalisées entre 0 et 1, dans l'ordre
+    /// rouge, vert, bleu et alpha.
+    pub fn to_normalized(self) -> [f32; 4] {
+        let normalize = |c: u8| f32::from(c) / f32::from(u8::MAX);
+
+        [
+            normalize(self.red),
+            normalize(self.green),
+            normalize(self.blue),
+            normalize(self.alpha),
+        ]
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.alpha == u8::MAX {
+            write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+        } else {
+            write!(f, "#{:02x}{:02x}{:02x}{:02x}", self.alpha, self.red, self.green, self.blue)
+        }
+    }
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self::rgba(0, 0, 0, 0)
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParsingError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s.strip_prefix('#').unwrap_or(s);
+
+        if !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ParsingError::InvalidString(String::from(s)));
+        }
+
+        let component = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16)
+            .map_err(|_| ParsingError::InvalidString(String::from(s)));
+
+        match hex.len() {
+            0 => Err(ParsingError::EmptyString),
+            6 => Ok(Self::rgb(component(0)?, component(2)?, component(4)?)),
+            8 => Ok(Self::rgba(component(2)?, component(4)?, component(6)?, component(0)?)),
+            _ => Err(ParsingError::InvalidString(String::from(s))),
+        }
+    }
+}
+
+/// Chevauchement entre les plages d'identifiants globaux de deux jeux de tuiles.
+///
+/// Les identifiants concernés sont associés au jeu possédant le plus grand
+/// `firstgid`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TileSetOverlap {
+    /// `firstgid` du jeu de tuiles dont la plage est recouverte.
+    pub first: u16,
+    /// `firstgid` du jeu de tuiles recouvrant la plage du premier.
+    pub second: u16,
+    /// Identifiants globaux appartenant aux deux jeux.
+    pub gids: RangeInclusive<u16>,
+}
+
+impl fmt::Display for TileSetOverlap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the tilesets with firstgid {} and {} both contain gids {} to {}",
+            self.first, self.second, self.gids.start(), self.gids.end(),
+        )
+    }
+}
+
+/// Renvoie les chevauchements entre les plages d'identifiants globaux des jeux
+/// de tuiles passés en paramètre, dans un ordre quelconque.
+pub(crate) fn tileset_overlaps(tilesets: &[TileSet]) -> Vec<TileSetOverlap> {
+    let mut sorted: Vec<&TileSet> = tilesets.iter().collect();
+    sorted.sort_by_key(|tileset| tileset.firstgid);
+
+    let mut overlaps = Vec::new();
+
+    for (i, first) in sorted.iter().enumerate() {
+        let last_gid = first.last_gid();
+
+        for second in sorted[i + 1..].iter().take_while(|second| second.firstgid <= last_gid) {
+            overlaps.push(TileSetOverlap {
+                first: first.firstgid,
+                second: second.firstgid,
+                gids: second.firstgid..=last_gid.min(second.last_gid()),
+            });
+        }
+    }
+
+    overlaps
+}
+
+/// Options du chargement d'une map.
+#[derive(Clone, Copy, Default)]
+pub struct LoadOptions<'a> {
+    /// Indique si le chargement échoue à la première valeur invalide.
+    ///
+    /// Dans le cas contraire, les valeurs invalides sont ignorées et chacune
+    /// d'elles est décrite par un [`Warning`].
+    pub strict: bool,
+    /// Source des fichiers référencés par la map, comme ses jeux de tuiles
+    /// externes et ses modèles d'objets.
+    pub resources: Option<&'a dyn ResourceReader>,
+    /// Projet dont les types personnalisés servent à résoudre les propriétés.
+    pub project: Option<&'a Project>,
+}
+
+impl<'a> LoadOptions<'a> {
+    /// Source des fichiers référencés, dont les chemins d'accès sont résolus
+    /// depuis sa racine.
+    pub(crate) fn root_resources(&self) -> Option<Resources<'a>> {
+        self.resources.map(|reader| Resources { dir: Path::new(""), reader })
+    }
+}
+
+impl fmt::Debug for LoadOptions<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LoadOptions")
+            .field("strict", &self.strict)
+            .field("project", &self.project)
+            .finish_non_exhaustive()
+    }
+}
+
 /// Contient toutes les données d'une map composée de tuiles.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Map {
     /// Contient un exemplaire de chaque jeu de tuiles.
     pub(crate) tilesets: Vec<TileSet>,
-    /// Contient pour chaque id global l'indice de son jeu de tuiles.
-    pub(crate) tileset_indexes: Vec<Option<usize>>,
     /// Taille de la map.
     pub size: Vector2<u16>,
     /// Taille en pixels des tuiles composant la map.
@@ -324,48 +797,274 @@ pub struct Map {
     pub orientation: Orientation,
     /// Axe de décalage de la map.
     pub stagger_axis: StaggerAxis,
+    /// Lignes ou colonnes décalées de la map.
+    pub stagger_index: StaggerIndex,
+    /// Longueur en pixels du côté plat des tuiles d'une map hexagonale.
+    pub hex_side_length: u16,
+    /// Propriétés personnalisées de la map.
+    pub properties: Properties,
+    /// Dossier contenant le fichier de la map, lorsque celle-ci a été chargée
+    /// depuis un chemin d'accès.
+    pub base_dir: Option<PathBuf>,
 }
 
 impl Map {
     /// Instancie une nouvelle map depuis les données d'un fichier `.tmx`.
     ///
+    /// Équivaut à [`Map::load_tmx_with`] avec les options par défaut : les valeurs
+    /// invalides sont ignorées, et les fichiers référencés par la map ne sont pas
+    /// chargés.
+    ///
     /// # Erreurs
     ///
     /// Cette fonction retourne une erreur si les données passées en paramètre ne
     /// sont pas au format XML.
     #[inline]
     pub fn load_tmx<D: AsRef<[u8]>>(data: D) -> Result<Self, TMXError> {
-        from_reader(data.as_ref())
+        Self::load_tmx_with(data.as_ref(), &LoadOptions::default()).map(|(map, _)| map)
+    }
+
+    /// Instancie une nouvelle map depuis un fichier `.tmx` lu au fur et à mesure
+    /// depuis le lecteur passé en paramètre, et renvoie les avertissements émis.
+    ///
+    /// Le document n'est jamais chargé entièrement en mémoire, ce qui permet de
+    /// lire une map depuis une connexion réseau ou un flux décompressé. Les
+    /// chemins d'accès des fichiers référencés par la map sont résolus depuis la
+    /// racine de la source de fichiers des options ; sans source, seule leur
+    /// référence est conservée.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si les données lues ne sont pas au
+    /// format XML, si la lecture échoue, si un fichier référencé ne peut être lu,
+    /// si une propriété ne correspond pas aux types du projet, ou, en mode
+    /// strict, si un attribut ou le contenu d'une balise ne peut être converti.
+    pub fn load_tmx_with<R: BufRead>(
+        reader: R,
+        options: &LoadOptions<'_>,
+    ) -> Result<(Self, Vec<Warning>), TMXError> {
+        Self::load_tmx_in(reader, options.root_resources(), options)
+    }
+
+    /// Instancie une nouvelle map depuis le fichier `.tmx` passé en paramètre, et
+    /// renvoie les avertissements émis.
+    ///
+    /// Le fichier et ceux qu'il référence sont lus depuis la source de fichiers
+    /// des options, ou depuis le système de fichiers si elles n'en précisent pas.
+    /// Les chemins d'accès des images, des jeux de tuiles externes et des modèles
+    /// d'objets sont résolus par rapport au fichier les référençant : les jeux de
+    /// tuiles externes et les modèles sont chargés avec la map, et le chemin
+    /// résolu de chaque image est renseigné dans [`Image::path`].
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si le fichier ne peut être lu, ou dans
+    /// les mêmes cas que [`Map::load_tmx_with`].
+    pub fn load_tmx_file<P: AsRef<Path>>(
+        path: P,
+        options: &LoadOptions<'_>,
+    ) -> Result<(Self, Vec<Warning>), TMXError> {
+        let path = path.as_ref();
+        let reader = options.resources.unwrap_or(&FileSystem);
+        let file = BufReader::new(reader.open(path)?);
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let resources = Resources { dir, reader };
+
+        let (mut map, warnings) = Self::load_tmx_in(file, Some(resources), options)?;
+        map.base_dir = Some(dir.to_path_buf());
+
+        Ok((map, warnings))
+    }
+
+    fn load_tmx_in<R: BufRead>(
+        reader: R,
+        resources: Option<Resources<'_>>,
+        options: &LoadOptions<'_>,
+    ) -> Result<(Self, Vec<Warning>), TMXError> {
+        let (map, warnings) = diagnostic::load_tmx(
+            reader, options.strict, resources, options.project, PhantomData::<Self>,
+        );
+        Ok((map?, warnings))
+    }
+
+    /// Instancie une nouvelle map depuis les données d'un fichier `.tmj`.
+    ///
+    /// Le contenu des calques de tuiles peut aussi bien être un tableau
+    /// d'identifiants qu'une chaîne de caractères encodée en base64, compressée ou
+    /// non. Équivaut à [`Map::load_tmj_with`] avec les options par défaut : les
+    /// jeux de tuiles externes ne sont pas chargés, seule leur référence est
+    /// conservée.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si les données passées en paramètre ne
+    /// sont pas au format JSON, si le contenu d'un calque ne peut être décodé, ou
+    /// si un morceau d'une map infinie contient des tuiles hors de la map.
+    #[inline]
+    pub fn load_tmj<D: AsRef<[u8]>>(data: D) -> Result<Self, TMJError> {
+        Self::load_tmj_with(data.as_ref(), &LoadOptions::default())
+    }
+
+    /// Instancie une nouvelle map depuis un fichier `.tmj` lu depuis le lecteur
+    /// passé en paramètre.
+    ///
+    /// Les jeux de tuiles externes sont chargés depuis la source de fichiers des
+    /// options, leur chemin d'accès étant résolu depuis sa racine, puis les
+    /// propriétés sont résolues à l'aide du projet des options. Le format JSON
+    /// n'étant pas lu valeur par valeur, le chargement échoue toujours à la
+    /// première valeur invalide, quel que soit le mode des options.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si les données lues ne décrivent pas une
+    /// map, si un jeu de tuiles référencé ne peut être lu, ou si une propriété ne
+    /// correspond pas aux types du projet (voir [`Project::resolve_properties`]).
+    pub fn load_tmj_with<R: Read>(reader: R, options: &LoadOptions<'_>) -> Result<Self, TMJError> {
+        Self::load_tmj_in(reader, options.root_resources(), options)
+    }
+
+    /// Instancie une nouvelle map depuis le fichier `.tmj` passé en paramètre.
+    ///
+    /// Le fichier et les jeux de tuiles qu'il référence sont lus depuis la source
+    /// de fichiers des options, ou depuis le système de fichiers si elles n'en
+    /// précisent pas. Les chemins d'accès des jeux de tuiles et des images sont
+    /// résolus par rapport au fichier les référençant, et le chemin résolu de
+    /// chaque image est renseigné dans [`Image::path`].
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si le fichier ne peut être lu, ou dans
+    /// les mêmes cas que [`Map::load_tmj_with`].
+    pub fn load_tmj_file<P: AsRef<Path>>(
+        path: P,
+        options: &LoadOptions<'_>,
+    ) -> Result<Self, TMJError> {
+        let path = path.as_ref();
+        let reader = options.resources.unwrap_or(&FileSystem);
+        let file = BufReader::new(reader.open(path).map_err(TMJError::io)?);
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let resources = Resources { dir, reader };
+
+        let mut map = Self::load_tmj_in(file, Some(resources), options)?;
+        map.base_dir = Some(dir.to_path_buf());
+
+        Ok(map)
+    }
+
+    fn load_tmj_in<R: Read>(
+        reader: R,
+        resources: Option<Resources<'_>>,
+        options: &LoadOptions<'_>,
+    ) -> Result<Self, TMJError> {
+        let mut map = serde_json::from_reader::<_, JsonMap>(reader)?.into_map(resources)?;
+
+        if let Some(project) = options.project {
+            project.resolve_map(&mut map).map_err(serde::de::Error::custom)?;
+        }
+
+        Ok(map)
+    }
+
+    /// Écrit la map au format `.tmx`.
+    ///
+    /// Le contenu du calque de tuiles est écrit avec l'encodage passé en paramètre,
+    /// tandis que les jeux de tuiles externes ne sont écrits que sous la forme d'une
+    /// référence vers leur fichier.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si l'écriture échoue, ou si l'algorithme
+    /// de compression demandé n'est pas disponible.
+    #[inline]
+    pub fn write_tmx<W: Write>(&self, writer: W, encoding: Encoding) -> Result<(), TMXError> {
+        serial::write_map(self, writer, encoding)
+    }
+
+    /// Écrit la map au format `.tmj`.
+    ///
+    /// Le calque de tuiles est écrit sous la forme d'un tableau avec l'encodage
+    /// [`Encoding::Csv`], et sous la forme d'une chaîne de caractères sinon. Le
+    /// format JSON ne permettant pas de décrire une image intégrée au fichier,
+    /// seule la source des images est écrite.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si l'écriture échoue, ou si l'algorithme
+    /// de compression demandé n'est pas disponible.
+    #[inline]
+    pub fn write_tmj<W: Write>(&self, writer: W, encoding: Encoding) -> Result<(), TMJError> {
+        json::write_map(self, writer, encoding)
     }
 
     /// Réordonne la liste des jeux de tuiles afin qu'ils soient dans l'ordre de
-    /// leur `firstgid`, puis associe pour chaque gid un jeu de tuiles.
+    /// leur `firstgid`, ce qui permet de retrouver le jeu d'un gid par recherche
+    /// dichotomique.
     pub(crate) fn reorder_tilesets(&mut self) {
-        self.tilesets.sort_unstable_by(|a, b| a.firstgid.cmp(&b.firstgid));
-
-        self.tileset_indexes.clear();
-        // Le gid 0 ne peut pas posséder de jeu de tuiles.
-        self.tileset_indexes.push(None);
+        self.tilesets.sort_by_key(|tileset| tileset.firstgid);
+    }
 
-        let mut last_gid = 0;
+    /// Renvoie l'identifiant du calque de tuiles écrit avec la map : le plus petit
+    /// identifiant qu'aucun groupe d'objets n'utilise.
+    pub(crate) fn tile_layer_id(&self) -> u16 {
+        (1..=u16::MAX)
+            .find(|&id| self.object_groups.iter().all(|group| group.id != id))
+            .unwrap_or(u16::MAX)
+    }
 
-        for (i, tileset) in self.tilesets.iter().enumerate() {
-            // On comble les trous entre le nouveau et le dernier jeu de tuiles.
-            for _ in last_gid..(tileset.firstgid - 1) {
-                self.tileset_indexes.push(None);
-            }
+    /// Renvoie les prochains identifiants libres de calque et d'objet, écrits
+    /// avec la map afin que Tiled n'attribue pas un identifiant déjà utilisé.
+    pub(crate) fn next_ids(&self) -> (u16, u16) {
+        let last_layer_id = self.object_groups
+            .iter()
+            .map(|group| group.id)
+            .fold(self.tile_layer_id(), u16::max);
+        let last_object_id = self.object_groups
+            .iter()
+            .flat_map(|group| &group.objects)
+            .map(|object| object.id)
+            .fold(0, u16::max);
+
+        (last_layer_id.saturating_add(1), last_object_id.saturating_add(1))
+    }
 
-            // On rajoute autant d'indices vers le jeu de tuiles que de tuiles que celui-ci
-            // peut contenir.
+    /// Renvoie l'indice du jeu de tuiles associé au gid passé en paramètre.
+    ///
+    /// Lorsque plusieurs jeux se chevauchent, le gid est associé à celui possédant
+    /// le plus grand `firstgid` inférieur ou égal à celui-ci.
+    fn tileset_index(&self, gid: u16) -> Option<usize> {
+        if gid == EMPTY_TILE {
+            return None;
+        }
 
-            let last_id = tileset.last_id();
+        let index = self.tilesets.partition_point(|tileset| tileset.firstgid <= gid);
+        let index = index.checked_sub(1)?;
 
-            for _ in 0..=last_id {
-                self.tileset_indexes.push(Some(i));
-            }
+        self.tilesets[index].gids().contains(&gid).then_some(index)
+    }
 
-            last_gid = tileset.firstgid + last_id;
+    /// Renvoie l'indice du jeu de tuiles auquel appartient le gid passé en
+    /// paramètre, parmi des jeux triés par `firstgid`.
+    ///
+    /// Contrairement à `tileset_index`, le nombre de tuiles des jeux externes non
+    /// chargés n'étant pas connu, tout gid qui les suit leur est attribué.
+    pub(crate) fn gid_owner(tilesets: &[TileSet], gid: u16) -> Option<usize> {
+        if gid == EMPTY_TILE {
+            return None;
         }
+
+        let index = tilesets.partition_point(|tileset| tileset.firstgid <= gid);
+        let index = index.checked_sub(1)?;
+        let tileset = &tilesets[index];
+        let unloaded = tileset.source.is_some() && tileset.path.is_none();
+
+        (unloaded || tileset.gids().contains(&gid)).then_some(index)
+    }
+
+    /// Renvoie les chevauchements entre les plages d'identifiants globaux des jeux
+    /// de tuiles de la map.
+    #[inline]
+    pub fn tileset_overlaps(&self) -> Vec<TileSetOverlap> {
+        tileset_overlaps(&self.tilesets)
     }
 
     /// Renvoie la liste des jeux de tuiles existants.
@@ -392,21 +1091,25 @@ impl Map {
         self.reorder_tilesets();
     }
 
-    /// Renvoie le jeu de tuiles associé au gid passé en paramètre.
+    /// Renvoie le jeu de tuiles associé au gid passé en paramètre, ou `None` si
+    /// celui-ci n'appartient à aucun jeu.
+    ///
+    /// Voir [`Map::tileset_overlaps`] lorsque plusieurs jeux se chevauchent.
     pub fn get_tileset(&self, gid: u16) -> Option<&TileSet> {
-        match self.tileset_indexes.get(usize::from(gid)) {
-            Some(&Some(i)) => self.tilesets.get(i),
-            _ => None,
-        }
+        self.tileset_index(gid).map(|i| &self.tilesets[i])
     }
 
     /// Renvoie une référence mutable du jeu de tuiles associé au gid passé en
     /// paramètre.
     pub fn get_tileset_mut(&mut self, gid: u16) -> Option<&mut TileSet> {
-        match self.tileset_indexes.get(usize::from(gid)) {
-            Some(&Some(i)) => self.tilesets.get_mut(i),
-            _ => None,
-        }
+        self.tileset_index(gid).map(move |i| &mut self.tilesets[i])
+    }
+
+    /// Renvoie les propriétés personnalisées de la tuile associée au gid passé en
+    /// paramètre.
+    pub fn tile_properties(&self, gid: u16) -> Option<&Properties> {
+        self.get_tileset(gid)
+            .and_then(|tileset| tileset.tile_properties(gid - tileset.firstgid))
     }
 
     /// Renvoie le jeu de tuiles possédant le firstgid le plus élevé.
@@ -461,45 +1164,190 @@ impl Map {
     ///
     /// Attention, ces coordonnées sont relatives à la position de la map dans le
     /// monde et représente le centre de la tuile.
+    ///
+    /// L'origine est le coin supérieur gauche du rectangle englobant la map, et
+    /// l'axe des ordonnées est orienté vers le haut. Sur une map isométrique, la
+    /// tuile (0, 0) occupe ainsi le sommet du losange formé par la map, à une
+    /// abscisse d'une demi-tuile par ligne de la map, et chaque colonne et chaque
+    /// ligne décale la tuile d'une demi-largeur et d'une demi-hauteur de tuile.
+    ///
+    /// Sur une map décalée ou hexagonale, les lignes ou colonnes désignées par
+    /// [`Map::stagger_index`] sont décalées d'une demi-tuile, et les tuiles se
+    /// chevauchent afin de n'être séparées que par leur côté plat, long de
+    /// [`Map::hex_side_length`] pixels sur une map hexagonale et nul sur une map
+    /// décalée. Comme dans Tiled, l'axe de décalage par défaut est l'axe y.
     pub fn to_world_coords(&self, map_coords: Point2<u16>) -> Point2<f32> {
-        let size = Vector2 {
-            x: f32::from(self.tile_size.x),
-            y: f32::from(self.tile_size.y),
-        };
-
-        let multiplier = match (self.orientation, self.stagger_axis) {
-            (Orientation::Hexagonal, StaggerAxis::XAxis) => Point2 {
-                x: size.x * 0.75,
-                y: size.y,
+        let size = self.world_tile_size();
+        let column = f32::from(map_coords.x);
+        let row = f32::from(map_coords.y);
+
+        // Coin supérieur gauche de la tuile, l'axe des ordonnées orienté vers le bas.
+        let corner = match self.orientation {
+            Orientation::Orthogonal => Point2 {
+                x: column * size.x,
+                y: row * size.y,
             },
-            (Orientation::Hexagonal, StaggerAxis::YAxis) => Point2 {
-                x: size.x,
-                y: size.y * 0.75,
+            Orientation::Isometric => Point2 {
+                x: (f32::from(self.size.y) + column - row - 1.0) * size.x / 2.0,
+                y: (column + row) * size.y / 2.0,
             },
-            _ => Point2::from(size),
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let step = self.cell_step();
+                let mut corner = Point2 { x: column * step.x, y: row * step.y };
+
+                match self.staggered_axis() {
+                    StaggerAxis::XAxis if self.is_staggered(map_coords.x) => {
+                        corner.y += size.y / 2.0;
+                    }
+                    StaggerAxis::YAxis if self.is_staggered(map_coords.y) => {
+                        corner.x += size.x / 2.0;
+                    }
+                    _ => (),
+                }
+
+                corner
+            }
         };
 
-        let mut coords = Point2 {
-            x: f32::from(map_coords.x) * multiplier.x,
-            y: -f32::from(map_coords.y) * multiplier.y,
+        Point2 {
+            x: corner.x + size.x / 2.0,
+            y: -(corner.y + size.y / 2.0),
+        }
+    }
+
+    /// Convertit les coordonnées du monde en coordonnées de la tuile de la map les
+    /// contenant, ou `None` si celles-ci se trouvent hors de la map.
+    ///
+    /// Il s'agit de l'inverse de [`Map::to_world_coords`] : les coordonnées sont
+    /// relatives à la position de la map dans le monde, et l'axe des ordonnées est
+    /// orienté vers le haut. Un point situé sur la frontière entre deux tuiles
+    /// appartient à l'une ou l'autre.
+    pub fn to_map_coords(&self, world_coords: Point2<f32>) -> Option<Point2<u16>> {
+        let size = self.world_tile_size();
+
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return None;
+        }
+
+        // La tuile approchée ignore le décalage des tuiles, qui ne dépasse pas une
+        // demi-tuile : la tuile recherchée est donc l'une de ses voisines.
+        let guess = if self.orientation == Orientation::Isometric {
+            let diagonal = (world_coords.x / (size.x / 2.0)) - f32::from(self.size.y);
+            let depth = -world_coords.y / (size.y / 2.0) - 1.0;
+
+            Point2 {
+                x: ((depth + diagonal) / 2.0).round() as i32,
+                y: ((depth - diagonal) / 2.0).round() as i32,
+            }
+        } else {
+            let step = self.cell_step();
+
+            Point2 {
+                x: ((world_coords.x - size.x / 2.0) / step.x).round() as i32,
+                y: ((-world_coords.y - size.y / 2.0) / step.y).round() as i32,
+            }
         };
 
-        match self.coords_stagger_axis(map_coords) {
-            StaggerAxis::YAxis => {
-                coords.y -= size.y / 2.0;
-                coords.x += size.x;
+        let mut best: Option<(f32, Point2<u16>)> = None;
+
+        for y in guess.y - 1..=guess.y + 1 {
+            for x in guess.x - 1..=guess.x + 1 {
+                let coords = match (u16::try_from(x), u16::try_from(y)) {
+                    (Ok(x), Ok(y)) if x < self.size.x && y < self.size.y => Point2 { x, y },
+                    _ => continue,
+                };
+
+                let center = self.to_world_coords(coords);
+                let offset = Vector2 {
+                    x: (world_coords.x - center.x).abs(),
+                    y: (world_coords.y - center.y).abs(),
+                };
+                let distance = self.cell_distance(offset);
+
+                if best.map_or(true, |(best, _)| distance < best) {
+                    best = Some((distance, coords));
+                }
+            }
+        }
+
+        best.filter(|&(distance, _)| distance <= 1.0).map(|(_, coords)| coords)
+    }
+
+    /// Renvoie la taille des tuiles de la map dans le monde.
+    #[inline]
+    fn world_tile_size(&self) -> Vector2<f32> {
+        Vector2 {
+            x: f32::from(self.tile_size.x),
+            y: f32::from(self.tile_size.y),
+        }
+    }
+
+    /// Renvoie la distance séparant deux tuiles consécutives sur chaque axe, sans
+    /// tenir compte de leur décalage.
+    fn cell_step(&self) -> Vector2<f32> {
+        let size = self.world_tile_size();
+        let side = self.side_length();
+
+        match (self.orientation, self.staggered_axis()) {
+            (Orientation::Staggered | Orientation::Hexagonal, StaggerAxis::XAxis) => Vector2 {
+                x: (size.x + side) / 2.0,
+                y: size.y,
             },
-            StaggerAxis::XAxis => {
-                coords.y -= size.y;
-                coords.x += size.x / 2.0;
+            (Orientation::Staggered | Orientation::Hexagonal, _) => Vector2 {
+                x: size.x,
+                y: (size.y + side) / 2.0,
             },
-            StaggerAxis::None => {
-                coords.y -= size.y / 2.0;
-                coords.x += size.x / 2.0;
+            _ => size,
+        }
+    }
+
+    /// Renvoie la distance normalisée entre le centre d'une tuile et un point
+    /// situé au décalage absolu passé en paramètre : celle-ci vaut au plus 1 si
+    /// le point appartient à la tuile, et au moins 1 sinon.
+    fn cell_distance(&self, offset: Vector2<f32>) -> f32 {
+        let size = self.world_tile_size();
+        let half = Vector2 { x: size.x / 2.0, y: size.y / 2.0 };
+        let side = self.side_length();
+
+        match (self.orientation, self.staggered_axis()) {
+            (Orientation::Orthogonal, _) => (offset.x / half.x).max(offset.y / half.y),
+            (Orientation::Isometric, _) => offset.x / half.x + offset.y / half.y,
+            // Hexagone dont le côté plat est horizontal, et devient un losange
+            // lorsque celui-ci est nul.
+            (_, StaggerAxis::XAxis) => {
+                let slope = (offset.x + offset.y * (size.x - side) / size.y) / half.x;
+                (offset.y / half.y).max(slope)
+            }
+            (_, _) => {
+                let slope = (offset.y + offset.x * (size.y - side) / size.x) / half.y;
+                (offset.x / half.x).max(slope)
             }
         }
+    }
+
+    /// Renvoie l'axe de décalage d'une map décalée ou hexagonale, l'axe y étant
+    /// utilisé par défaut.
+    #[inline]
+    pub(crate) fn staggered_axis(&self) -> StaggerAxis {
+        match self.stagger_axis {
+            StaggerAxis::None => StaggerAxis::YAxis,
+            axis => axis,
+        }
+    }
+
+    /// Renvoie la longueur du côté plat des tuiles, nulle sur une map décalée.
+    #[inline]
+    fn side_length(&self) -> f32 {
+        match self.orientation {
+            Orientation::Hexagonal => f32::from(self.hex_side_length),
+            _ => 0.0,
+        }
+    }
 
-        coords
+    /// Indique si la ligne ou la colonne passée en paramètre est décalée.
+    #[inline]
+    pub(crate) fn is_staggered(&self, index: u16) -> bool {
+        (index % 2 == 1) == (self.stagger_index == StaggerIndex::Odd)
     }
 
     /// Renvoie l'axe de décalage de la tuile passée en paramètre.
@@ -510,10 +1358,12 @@ impl Map {
 
     /// Renvoie l'axe de décalage de la tuile dont les coordonnées sont passés en
     /// paramètre.
+    ///
+    /// Les lignes ou colonnes décalées sont désignées par [`Map::stagger_index`].
     pub fn coords_stagger_axis(&self, coords: Point2<u16>) -> StaggerAxis {
         match self.stagger_axis {
-            axis @ StaggerAxis::XAxis if coords.x % 2 == 1 => axis,
-            axis @ StaggerAxis::YAxis if coords.y % 2 == 1 => axis,
+            axis @ StaggerAxis::XAxis if self.is_staggered(coords.x) => axis,
+            axis @ StaggerAxis::YAxis if self.is_staggered(coords.y) => axis,
             _ => StaggerAxis::None,
         }
     }
@@ -523,19 +1373,25 @@ impl Default for Map {
     fn default() -> Self {
         Self {
             tilesets: Vec::new(),
-            tileset_indexes: Vec::new(),
             size: Vector2 { x: 0, y: 0 },
             tile_size: Vector2 { x: 0, y: 0 },
             tiles: Vec::new(),
             object_groups: Vec::new(),
             orientation: Orientation::Orthogonal,
             stagger_axis: StaggerAxis::None,
+            stagger_index: StaggerIndex::Odd,
+            hex_side_length: 0,
+            properties: Properties::new(),
+            base_dir: None,
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::field_reassign_with_default)]
 mod tests {
+    use std::fs;
+
     use mint::Vector2;
     use super::*;
 
@@ -562,19 +1418,156 @@ mod tests {
 
         let should_be = Map {
             tilesets: vec![],
-            tileset_indexes: vec![None],
             size: Vector2 { x: 10, y: 10 },
             tile_size: Vector2 {x: 24, y: 24 },
             tiles: vec![0, 0, 0, 3, 2, 1],
             object_groups: vec![],
             orientation: Orientation::Isometric,
-            stagger_axis: StaggerAxis::XAxis
+            stagger_axis: StaggerAxis::XAxis,
+            stagger_index: StaggerIndex::Odd,
+            hex_side_length: 0,
+            properties: Properties::new(),
+            base_dir: None,
         };
 
         let map = Map::load_tmx(src).unwrap();
         assert_eq!(map, should_be);
     }
 
+    #[test]
+    fn test_load_tmx_reader() {
+        use std::io::Write;
+        use std::net::{TcpListener, TcpStream};
+        use std::thread;
+
+        use flate2::Compression as GzCompression;
+        use flate2::read::GzDecoder;
+        use flate2::write::GzEncoder;
+
+        let src = r#"<map orientation="orthogonal" width="3" height="1" tilewidth="8">
+            <layer id="1" name="Ground" width="3" height="1">
+                <data encoding="csv">0,0,0</data>
+            </layer>
+            <properties>
+                <property name="count" type="int" value="many"/>
+            </properties>
+        </map>"#;
+        let should_be = Map::load_tmx(src).unwrap();
+        let options = LoadOptions::default();
+        let strict = LoadOptions { strict: true, ..options };
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            stream.write_all(src.as_bytes()).unwrap();
+        });
+
+        let stream = TcpStream::connect(address).unwrap();
+        let (map, _) = Map::load_tmx_with(BufReader::new(stream), &options).unwrap();
+        assert_eq!(map, should_be);
+        server.join().unwrap();
+
+        let mut encoder = GzEncoder::new(Vec::new(), GzCompression::default());
+        encoder.write_all(src.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+        let decoder = BufReader::new(GzDecoder::new(compressed.as_slice()));
+        assert_eq!(Map::load_tmx_with(decoder, &options).unwrap().0, should_be);
+
+        let small_buffer = BufReader::with_capacity(1, src.as_bytes());
+        let (_, warnings) = Map::load_tmx_with(small_buffer, &options).unwrap();
+        let (_, should_be) = Map::load_tmx_with(src.as_bytes(), &options).unwrap();
+        assert_eq!(warnings, should_be);
+        assert_eq!(warnings[0].location.line, 6);
+
+        let error = Map::load_tmx_with(BufReader::with_capacity(1, src.as_bytes()), &strict);
+        assert!(matches!(error, Err(TMXError::Attribute { .. })));
+    }
+
+    #[test]
+    fn test_load_tmx_file() {
+        let dir = std::env::temp_dir().join(format!("tiled-load-tmx-file-{}", std::process::id()));
+        let write = |path: &str, content: &str| {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+
+        write("maps/level.tmx", r#"
+        <map orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" source="../tilesets/terrain.tsx"/>
+            <layer id="1" name="Ground" width="2" height="1">
+                <data encoding="csv">1,4</data>
+            </layer>
+            <objectgroup id="2" name="Items">
+                <object id="1" template="../templates/chest.tx" x="8" y="0">
+                    <properties>
+                        <property name="gold" type="int" value="20"/>
+                    </properties>
+                </object>
+            </objectgroup>
+        </map>"#);
+        write("tilesets/terrain.tsx", r#"
+        <tileset name="terrain" tilewidth="8" tileheight="8" tilecount="4" columns="2">
+            <image source="../images/terrain.png" width="16" height="16"/>
+        </tileset>"#);
+        write("templates/chest.tx", r#"
+        <template>
+            <tileset firstgid="1" source="../tilesets/terrain.tsx"/>
+            <object gid="3" width="8" height="8">
+                <properties>
+                    <property name="gold" type="int" value="10"/>
+                    <property name="locked" type="bool" value="true"/>
+                </properties>
+            </object>
+        </template>"#);
+
+        let options = LoadOptions::default();
+        let (map, warnings) = Map::load_tmx_file(dir.join("maps/level.tmx"), &options).unwrap();
+        let tileset = &map.tilesets()[0];
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(map.base_dir, Some(dir.join("maps")));
+        assert_eq!(tileset.name, "terrain");
+        assert_eq!(tileset.count, 4);
+        assert_eq!(tileset.source.as_deref(), Some("../tilesets/terrain.tsx"));
+        assert_eq!(tileset.path, Some(dir.join("tilesets/terrain.tsx")));
+
+        match &tileset.origin {
+            Some(TilesOrigin::Image(image)) => {
+                assert_eq!(image.source, "../images/terrain.png");
+                assert_eq!(image.path, Some(dir.join("images/terrain.png")));
+            }
+            origin => panic!("unexpected origin: {:?}", origin),
+        }
+
+        let object = &map.object_groups[0].objects[0];
+        assert_eq!(object.gid, 3);
+        assert_eq!(object.size, Vector2 { x: 8, y: 8 });
+        assert_eq!(object.properties["gold"], PropertyValue::Int(20));
+        assert_eq!(object.properties["locked"], PropertyValue::Bool(true));
+
+        write("maps/broken.tmx", r#"<map orientation="orthogonal" width="2x" height="1"/>"#);
+        let (_, warnings) = Map::load_tmx_file(dir.join("maps/broken.tmx"), &options).unwrap();
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+
+        let strict = LoadOptions { strict: true, ..options };
+        let error = Map::load_tmx_file(dir.join("maps/broken.tmx"), &strict).unwrap_err();
+        assert!(matches!(error, TMXError::Attribute { .. }), "{}", error);
+
+        fs::remove_file(dir.join("tilesets/terrain.tsx")).unwrap();
+        match Map::load_tmx_file(dir.join("maps/level.tmx"), &options).unwrap_err() {
+            TMXError::MissingResource { location, source, .. } => {
+                assert_eq!(source, "../tilesets/terrain.tsx");
+                assert_eq!(location.path, "map/tileset[source=../tilesets/terrain.tsx]");
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+
+        assert!(Map::load_tmx(fs::read(dir.join("maps/level.tmx")).unwrap()).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn tilesets_test() {
         let mut map = Map::default();
@@ -614,6 +1607,45 @@ mod tests {
         tileset_gids_association(&map, &tileset_collection);
 
         assert_eq!(map.get_tileset(12), None);
+        assert!(map.tileset_overlaps().is_empty());
+    }
+
+    #[test]
+    fn test_tileset_lookup_edge_cases() {
+        let mut map = Map::default();
+        map.add_tilesets(vec![
+            TileSet { firstgid: 0, count: 4, ..Default::default() },
+            TileSet { firstgid: 3, count: 4, ..Default::default() },
+            TileSet { firstgid: 4, count: 1, ..Default::default() },
+            TileSet { count: 8, ..Default::default() },
+        ]);
+
+        assert_eq!(map.get_tileset(0), None);
+        assert_eq!(map.get_tileset(1).unwrap().firstgid, 0);
+        assert_eq!(map.get_tileset(3).unwrap().firstgid, 3);
+        assert_eq!(map.get_tileset(4).unwrap().firstgid, 4);
+        assert_eq!(map.get_tileset(5), None);
+        assert_eq!(map.get_tileset(u16::MAX).unwrap().firstgid, u16::MAX);
+        assert_eq!(map.get_tileset_mut(2).unwrap().firstgid, 0);
+        assert_eq!(map.tile_properties(6), None);
+
+        assert_eq!(map.tileset_overlaps(), vec![
+            TileSetOverlap { first: 0, second: 3, gids: 3..=3 },
+            TileSetOverlap { first: 3, second: 4, gids: 4..=4 },
+        ]);
+
+        let src = r#"<map orientation="orthogonal" width="1" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="foo" tilewidth="8" tileheight="8" tilecount="4" columns="2"/>
+            <tileset firstgid="3" name="bar" tilewidth="8" tileheight="8" tilecount="4" columns="2"/>
+        </map>"#;
+
+        let (map, warnings) = Map::load_tmx_with(src.as_bytes(), &LoadOptions::default()).unwrap();
+        assert_eq!(map.tilesets().len(), 2);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].field, "tileset");
+        assert!(warnings[0].message.contains("gids 3 to 4"), "{}", warnings[0]);
+        let strict = LoadOptions { strict: true, ..LoadOptions::default() };
+        assert!(Map::load_tmx_with(src.as_bytes(), &strict).is_err());
     }
 
     #[test]
@@ -644,11 +1676,56 @@ mod tests {
         assert_eq!(map.to_world_coords([3, 1].into()), [56.0, -24.0].into());
     }
 
+    #[test]
+    fn isometric_to_world_coords_test() {
+        let mut map = Map {
+            orientation: Orientation::Isometric,
+            size: Vector2 { x: 4, y: 3 },
+            tile_size: Vector2 { x: 32, y: 16 },
+            ..Default::default()
+        };
+
+        assert_eq!(map.to_world_coords([0, 0].into()), [48.0, -8.0].into());
+        assert_eq!(map.to_world_coords([1, 0].into()), [64.0, -16.0].into());
+        assert_eq!(map.to_world_coords([0, 1].into()), [32.0, -16.0].into());
+        assert_eq!(map.to_world_coords([3, 2].into()), [64.0, -48.0].into());
+        assert_eq!(map.to_world_coords([0, 2].into()), [16.0, -24.0].into());
+
+        // Le décalage des maps hexagonales ne s'applique pas.
+        map.stagger_axis = StaggerAxis::XAxis;
+        assert_eq!(map.to_world_coords([1, 0].into()), [64.0, -16.0].into());
+    }
+
+    #[test]
+    fn staggered_to_world_coords_test() {
+        let mut map = Map {
+            orientation: Orientation::Staggered,
+            size: Vector2 { x: 4, y: 4 },
+            tile_size: Vector2 { x: 32, y: 16 },
+            ..Default::default()
+        };
+
+        assert_eq!(map.to_world_coords([0, 0].into()), [16.0, -8.0].into());
+        assert_eq!(map.to_world_coords([0, 1].into()), [32.0, -16.0].into());
+        assert_eq!(map.to_world_coords([1, 1].into()), [64.0, -16.0].into());
+        assert_eq!(map.to_world_coords([0, 2].into()), [16.0, -24.0].into());
+
+        map.stagger_index = StaggerIndex::Even;
+        assert_eq!(map.to_world_coords([0, 0].into()), [32.0, -8.0].into());
+        assert_eq!(map.to_world_coords([0, 1].into()), [16.0, -16.0].into());
+
+        map.stagger_axis = StaggerAxis::XAxis;
+        assert_eq!(map.to_world_coords([0, 0].into()), [16.0, -16.0].into());
+        assert_eq!(map.to_world_coords([1, 0].into()), [32.0, -8.0].into());
+        assert_eq!(map.to_world_coords([2, 1].into()), [48.0, -32.0].into());
+    }
+
     #[test]
     fn hexagonal_to_world_coords_test() {
         let mut map = Map::default();
         map.orientation = Orientation::Hexagonal;
         map.tile_size = TEST_SIZE;
+        map.hex_side_length = 8;
 
         let even_coords = [2, 2].into();
         let x_odd_coords = [3, 2].into();
@@ -661,6 +1738,109 @@ mod tests {
         map.stagger_axis = StaggerAxis::YAxis;
         assert_eq!(map.to_world_coords(even_coords), [40.0, -32.0].into());
         assert_eq!(map.to_world_coords(y_odd_coords), [48.0, -44.0].into());
+
+        map.stagger_index = StaggerIndex::Even;
+        assert_eq!(map.to_world_coords(even_coords), [48.0, -32.0].into());
+        assert_eq!(map.to_world_coords(y_odd_coords), [40.0, -44.0].into());
+
+        map.stagger_axis = StaggerAxis::XAxis;
+        assert_eq!(map.to_world_coords(even_coords), [32.0, -48.0].into());
+        assert_eq!(map.to_world_coords(x_odd_coords), [44.0, -40.0].into());
+
+        map.tile_size = Vector2 { x: 28, y: 32 };
+        map.hex_side_length = 16;
+        map.stagger_index = StaggerIndex::Odd;
+        assert_eq!(map.to_world_coords([1, 0].into()), [36.0, -32.0].into());
+        assert_eq!(map.to_world_coords([2, 1].into()), [58.0, -48.0].into());
+    }
+
+    /// Vérifie que chaque tuile de la map est retrouvée depuis son centre et
+    /// depuis un point proche de chacun de ses coins.
+    fn assert_map_coords_round_trip(map: &Map, inset: Vector2<f32>) {
+        for y in 0..map.size.y {
+            for x in 0..map.size.x {
+                let coords = Point2 { x, y };
+                let center = map.to_world_coords(coords);
+
+                for &(dx, dy) in &[(0.0, 0.0), (-1.0, -1.0), (-1.0, 1.0), (1.0, -1.0), (1.0, 1.0)] {
+                    let point = Point2 { x: center.x + dx * inset.x, y: center.y + dy * inset.y };
+                    assert_eq!(map.to_map_coords(point), Some(coords), "{:?}", point);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn to_map_coords_test() {
+        let mut map = Map {
+            size: Vector2 { x: 4, y: 3 },
+            tile_size: TEST_SIZE,
+            ..Default::default()
+        };
+
+        assert_eq!(map.to_map_coords([56.0, -24.0].into()), Some([3, 1].into()));
+        assert_eq!(map.to_map_coords([0.5, -0.5].into()), Some([0, 0].into()));
+        assert_eq!(map.to_map_coords([63.5, -47.5].into()), Some([3, 2].into()));
+        assert_eq!(map.to_map_coords([-0.5, -8.0].into()), None);
+        assert_eq!(map.to_map_coords([8.0, 0.5].into()), None);
+        assert_eq!(map.to_map_coords([64.5, -8.0].into()), None);
+        assert_map_coords_round_trip(&map, [7.0, 7.0].into());
+
+        map.orientation = Orientation::Hexagonal;
+        map.hex_side_length = 8;
+
+        map.stagger_axis = StaggerAxis::XAxis;
+        assert_map_coords_round_trip(&map, [3.0, 7.0].into());
+        // Coin inférieur droit de la tuile (0, 0), appartenant à la tuile (1, 0).
+        assert_eq!(map.to_map_coords([15.0, -15.0].into()), Some([1, 0].into()));
+        assert_eq!(map.to_map_coords([1.0, -1.0].into()), None);
+
+        map.stagger_axis = StaggerAxis::YAxis;
+        assert_map_coords_round_trip(&map, [7.0, 3.0].into());
+        assert_eq!(map.to_map_coords([15.0, -15.0].into()), Some([0, 1].into()));
+
+        map.stagger_index = StaggerIndex::Even;
+        assert_map_coords_round_trip(&map, [3.0, 3.0].into());
+
+        map.orientation = Orientation::Staggered;
+        assert_map_coords_round_trip(&map, [3.0, 3.0].into());
+        map.stagger_axis = StaggerAxis::XAxis;
+        assert_map_coords_round_trip(&map, [3.0, 3.0].into());
+        map.stagger_index = StaggerIndex::Odd;
+        assert_map_coords_round_trip(&map, [3.0, 3.0].into());
+        // Coin supérieur gauche de la tuile (1, 0), hors de son losange.
+        assert_eq!(map.to_map_coords([8.5, -8.5].into()), Some([0, 0].into()));
+
+        map.orientation = Orientation::Isometric;
+        map.stagger_axis = StaggerAxis::None;
+        assert_map_coords_round_trip(&map, [3.0, 3.0].into());
+        assert_eq!(map.to_map_coords([24.0, -1.0].into()), Some([0, 0].into()));
+        // Coin supérieur gauche du rectangle englobant, hors du losange.
+        assert_eq!(map.to_map_coords([1.0, -1.0].into()), None);
+        // Juste sous la pointe droite de la tuile (1, 0), dans la tuile (2, 0).
+        assert_eq!(map.to_map_coords([40.0, -16.5].into()), Some([2, 0].into()));
+        assert_eq!(map.to_map_coords([39.5, -16.0].into()), Some([1, 0].into()));
+
+        assert_eq!(Map::default().to_map_coords([0.0, 0.0].into()), None);
+    }
+
+    #[test]
+    fn color_from_str_test() {
+        assert_eq!("#ff00ff".parse(), Ok(Color::rgb(255, 0, 255)));
+        assert_eq!("ff00ff".parse(), Ok(Color::rgb(255, 0, 255)));
+        assert_eq!("#80102030".parse(), Ok(Color::rgba(16, 32, 48, 128)));
+
+        assert_eq!("".parse::<Color>(), Err(ParsingError::EmptyString));
+        assert_eq!("#".parse::<Color>(), Err(ParsingError::EmptyString));
+        assert_eq!("#ff00f".parse::<Color>(), Err(ParsingError::InvalidString("#ff00f".into())));
+        assert_eq!("#gg00ff".parse::<Color>(), Err(ParsingError::InvalidString("#gg00ff".into())));
+        assert_eq!("#+f00ff".parse::<Color>(), Err(ParsingError::InvalidString("#+f00ff".into())));
+    }
+
+    #[test]
+    fn color_to_normalized_test() {
+        assert_eq!(Color::rgba(255, 0, 255, 0).to_normalized(), [1.0, 0.0, 1.0, 0.0]);
+        assert_eq!(Color::rgb(51, 102, 0).to_normalized(), [0.2, 0.4, 0.0, 1.0]);
     }
 
     #[test]
@@ -680,5 +1860,9 @@ mod tests {
         assert_eq!(map.coords_stagger_axis([0, 0].into()), StaggerAxis::None);
         assert_eq!(map.coords_stagger_axis([1, 0].into()), StaggerAxis::None);
         assert_eq!(map.coords_stagger_axis([0, 1].into()), StaggerAxis::YAxis);
+
+        map.stagger_index = StaggerIndex::Even;
+        assert_eq!(map.coords_stagger_axis([0, 0].into()), StaggerAxis::YAxis);
+        assert_eq!(map.coords_stagger_axis([0, 1].into()), StaggerAxis::None);
     }
 }
\ No newline at end of file
@@ -1,198 +1,1636 @@
-use std::collections::HashMap;
+use std::collections::BTreeSet;
 use std::fmt::{self, Formatter};
+use std::io::Read;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+use mint::{Point2, Vector2};
 
 use serde::{Deserialize, Deserializer};
-use serde::de::{MapAccess, Visitor};
+use serde::de::{self, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+
+use crate::data::{self, Color, Compression, EMPTY_TILE, Image, Map, Object, ObjectGroup,
+                  ParsingError, Properties, PropertyValue, Tile, TileSet, TilesOrigin};
+use crate::diagnostic::{ContextSeed, LoadContext};
+use crate::events::{Chunk, LayerInfo, MapBuilder, MapHandler, MapInfo, ObjectGroupInfo};
+
+/// Valeur lue à l'aide du contexte du chargement en cours.
+pub(crate) trait ContextDeserialize<'de>: Sized {
+    /// Lit la valeur depuis le désérialiseur passé en paramètre, en signalant les
+    /// problèmes rencontrés au contexte.
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>;
+}
+
+impl<'de, T> ContextSeed<'de> for PhantomData<T>
+where T: ContextDeserialize<'de>
+{
+    type Value = T;
+
+    #[inline]
+    fn deserialize_in<D>(
+        self,
+        context: &mut LoadContext<'_>,
+        deserializer: D,
+    ) -> Result<T, D::Error>
+    where D: Deserializer<'de>
+    {
+        T::deserialize_in(context, deserializer)
+    }
+}
+
+/// Graine accompagnée du contexte du chargement en cours.
+///
+/// Elle permet de transmettre une [`ContextSeed`] à serde, et sert de visiteur
+/// aux graines transmettant leur contenu au gestionnaire d'événements.
+struct InContext<'c, 'a, S> {
+    context: &'c mut LoadContext<'a>,
+    seed: S,
+}
+
+impl<'de, S> DeserializeSeed<'de> for InContext<'_, '_, S>
+where S: ContextSeed<'de>
+{
+    type Value = S::Value;
+
+    #[inline]
+    fn deserialize<D>(self, deserializer: D) -> Result<S::Value, D::Error>
+    where D: Deserializer<'de>
+    {
+        self.seed.deserialize_in(self.context, deserializer)
+    }
+}
+
+/// Lit une suite de balises consécutives portant le même nom.
+struct ListVisitor<'c, 'a, T> {
+    context: &'c mut LoadContext<'a>,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T> Visitor<'de> for ListVisitor<'_, '_, T>
+where T: ContextDeserialize<'de>
+{
+    type Value = Vec<T>;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a sequence of tiled elements")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where A: SeqAccess<'de>
+    {
+        let context = self.context;
+        let mut list = Vec::new();
+
+        while let Some(value) = seq.next_element_seed(InContext {
+            context: &mut *context,
+            seed: PhantomData,
+        })? {
+            list.push(value);
+        }
+
+        Ok(list)
+    }
+}
+
+impl<'de, T> ContextDeserialize<'de> for Vec<T>
+where T: ContextDeserialize<'de>
+{
+    #[inline]
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_seq(ListVisitor { context, marker: PhantomData })
+    }
+}
+
+trait MapAccessExt<'de>: MapAccess<'de> {
+    /// Tente de lire la valeur suivante contenue dans la table.
+    ///
+    /// Si la valeur ne peut être convertie, celle-ci est signalée au contexte en
+    /// rappelant la clef associée : le chargement échoue en mode strict, et la
+    /// valeur est ignorée sinon.
+    fn read_value<F>(
+        &mut self,
+        context: &mut LoadContext<'_>,
+        key: &str,
+    ) -> Result<Option<F>, Self::Error>
+    where F: Deserialize<'de>
+    {
+        match self.next_value() {
+            Ok(value) => Ok(Some(value)),
+            Err(e) => context.report_error(key, e).map(|_| None),
+        }
+    }
+
+    /// Tente de lire la valeur suivante contenue dans la table à l'aide de la
+    /// graine passée en paramètre.
+    ///
+    /// Voir [`MapAccessExt::read_value`] lorsque la valeur est invalide.
+    fn read_value_seed<S>(
+        &mut self,
+        context: &mut LoadContext<'_>,
+        key: &str,
+        seed: S,
+    ) -> Result<Option<S::Value>, Self::Error>
+    where S: ContextSeed<'de>
+    {
+        match self.next_value_seed(InContext { context: &mut *context, seed }) {
+            Ok(value) => Ok(Some(value)),
+            Err(e) => context.report_error(key, e).map(|_| None),
+        }
+    }
+
+    /// Tente de lire la balise suivante contenue dans la table à l'aide du
+    /// contexte.
+    ///
+    /// Voir [`MapAccessExt::read_value`] lorsque la valeur est invalide.
+    #[inline]
+    fn read_element<F>(
+        &mut self,
+        context: &mut LoadContext<'_>,
+        key: &str,
+    ) -> Result<Option<F>, Self::Error>
+    where F: ContextDeserialize<'de>
+    {
+        self.read_value_seed(context, key, PhantomData)
+    }
+
+    /// Tente d'enregistrer la valeur suivante contenue dans la table dans la
+    /// variable passée en paramètre.
+    ///
+    /// Voir [`MapAccessExt::read_value`] lorsque la valeur est invalide.
+    fn save_value<F>(
+        &mut self,
+        context: &mut LoadContext<'_>,
+        var: &mut F,
+        key: &str,
+    ) -> Result<(), Self::Error>
+    where F: Deserialize<'de>
+    {
+        if let Some(value) = self.read_value(context, key)? {
+            *var = value;
+        }
+
+        Ok(())
+    }
+
+    /// Tente d'enregistrer la balise suivante contenue dans la table dans la
+    /// variable passée en paramètre.
+    ///
+    /// Voir [`MapAccessExt::read_value`] lorsque la valeur est invalide.
+    fn save_element<F>(
+        &mut self,
+        context: &mut LoadContext<'_>,
+        var: &mut F,
+        key: &str,
+    ) -> Result<(), Self::Error>
+    where F: ContextDeserialize<'de>
+    {
+        if let Some(value) = self.read_element(context, key)? {
+            *var = value;
+        }
+
+        Ok(())
+    }
+
+    /// Tente d'enregistrer la balise suivante contenue dans la table dans l'option
+    /// passée en paramètre.
+    ///
+    /// Voir [`MapAccessExt::read_value`] lorsque la valeur est invalide.
+    fn save_some<F>(
+        &mut self,
+        context: &mut LoadContext<'_>,
+        var: &mut Option<F>,
+        key: &str,
+    ) -> Result<(), Self::Error>
+    where F: ContextDeserialize<'de>
+    {
+        if let Some(value) = self.read_element(context, key)? {
+            *var = Some(value);
+        }
+
+        Ok(())
+    }
+
+    /// Tente d'enregistrer la liste de propriétés suivante contenue dans la table
+    /// dans la variable passée en paramètre.
+    fn save_properties(
+        &mut self,
+        context: &mut LoadContext<'_>,
+        var: &mut Properties,
+        key: &str,
+    ) -> Result<(), Self::Error> {
+        if let Some(PropertiesData(properties)) = self.read_element(context, key)? {
+            *var = properties;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de, T> MapAccessExt<'de> for T where T: MapAccess<'de> {}
+
+struct ColorVisitor;
+
+impl<'de> Visitor<'de> for ColorVisitor {
+    type Value = Color;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled color")
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where E: de::Error
+    {
+        v.parse().map_err(E::custom)
+    }
+}
+
+impl<'de> Deserialize<'de> for Color {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_str(ColorVisitor)
+    }
+}
+
+/// Contenu encodé d'une balise `<data>`.
+#[derive(Debug, Default)]
+struct Data {
+    /// Encodage utilisé pour le contenu.
+    encoding: Option<String>,
+    /// Algorithme de compression utilisé pour le contenu.
+    compression: Option<String>,
+    /// Contenu textuel de la balise.
+    content: String,
+}
+
+impl Data {
+    /// Décode le contenu binaire de la balise.
+    fn decode_bytes(&self) -> Result<Vec<u8>, ParsingError> {
+        match self.encoding.as_deref() {
+            Some("base64") => decode_base64_data(&self.content),
+            Some(encoding) => Err(ParsingError::InvalidString(String::from(encoding))),
+            None => Err(ParsingError::EmptyString),
+        }
+    }
+
+    /// Décode le contenu passé en paramètre en une liste d'identifiants globaux
+    /// de tuiles, selon l'encodage et l'algorithme de compression de la balise.
+    ///
+    /// Au format csv, chaque valeur invalide est signalée puis remplacée par une
+    /// tuile vide. Un contenu ne pouvant être décodé est ignoré, et un nombre de
+    /// tuiles ne correspondant pas à la taille passée en paramètre est signalé.
+    fn decode_tiles<E: de::Error>(
+        &self,
+        context: &mut LoadContext<'_>,
+        content: &str,
+        size: Vector2<u16>,
+    ) -> Result<Option<Vec<u16>>, E> {
+        let encoding = self.encoding.as_deref();
+        let tiles = match encoding {
+            None | Some("csv") => {
+                decode_csv_data(content, |e| context.report_decoding("data", e))?
+            }
+            _ => match decode_tiles_data(content, encoding, self.compression.as_deref()) {
+                Ok(tiles) => tiles,
+                Err(e) => return context.report_decoding("data", e).map(|_| None),
+            },
+        };
+
+        let expected = usize::from(size.x) * usize::from(size.y);
+
+        if tiles.len() != expected {
+            let message = format!("expected {} tiles, found {}", expected, tiles.len());
+            context.report_decoding("data", message)?;
+        }
+
+        Ok(Some(tiles))
+    }
+}
+
+struct DataVisitor<'c, 'a> {
+    context: &'c mut LoadContext<'a>,
+}
+
+impl<'de> Visitor<'de> for DataVisitor<'_, '_> {
+    type Value = Data;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled data")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: MapAccess<'de>
+    {
+        self.context.within("data", |context| {
+            let mut data = Data::default();
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "encoding" => map.save_value(context, &mut data.encoding, &key)?,
+                    "compression" => map.save_value(context, &mut data.compression, &key)?,
+                    "$value" => map.save_value(context, &mut data.content, &key)?,
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            Ok(data)
+        })
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+    where E: de::Error
+    {
+        Ok(Data { content: String::from(v), ..Default::default() })
+    }
+}
+
+impl<'de> ContextDeserialize<'de> for Data {
+    #[inline]
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(DataVisitor { context })
+    }
+}
+
+/// Contenu d'une balise `<property>`.
+#[derive(Debug, Default)]
+struct Property {
+    /// Nom de la propriété.
+    name: String,
+    /// Type de la propriété.
+    property_type: Option<String>,
+    /// Nom de la classe de la propriété.
+    custom_type: Option<String>,
+    /// Valeur textuelle de la propriété.
+    value: String,
+    /// Membres de la propriété lorsqu'il s'agit d'une classe.
+    properties: Properties,
+}
+
+impl Property {
+    /// Convertit la valeur textuelle de la propriété selon son type.
+    fn parse_value(self) -> Result<PropertyValue, ParsingError> {
+        fn parse<T: FromStr>(value: &str) -> Result<T, ParsingError> {
+            value.parse().map_err(|_| ParsingError::InvalidString(String::from(value)))
+        }
+
+        let value = match self.property_type.as_deref() {
+            None | Some("string") => PropertyValue::String(self.value),
+            Some("int") => parse(&self.value).map(PropertyValue::Int)?,
+            Some("float") => parse(&self.value).map(PropertyValue::Float)?,
+            Some("bool") => parse(&self.value).map(PropertyValue::Bool)?,
+            Some("color") if self.value.is_empty() => PropertyValue::Color(Color::default()),
+            Some("color") => self.value.parse().map(PropertyValue::Color)?,
+            Some("file") => PropertyValue::File(self.value),
+            Some("object") => parse(&self.value).map(PropertyValue::Object)?,
+            Some("class") => PropertyValue::Class {
+                property_type: String::new(),
+                properties: self.properties,
+            },
+            Some(property_type) => {
+                return Err(ParsingError::InvalidString(String::from(property_type)));
+            }
+        };
+
+        Ok(value.with_custom_type(self.custom_type))
+    }
+}
+
+/// Propriété convertie, dont la valeur est absente si elle a été ignorée.
+#[derive(Debug)]
+struct PropertyData {
+    /// Nom de la propriété.
+    name: String,
+    /// Valeur convertie de la propriété.
+    value: Option<PropertyValue>,
+}
+
+struct PropertyVisitor<'c, 'a> {
+    context: &'c mut LoadContext<'a>,
+}
+
+impl<'de> Visitor<'de> for PropertyVisitor<'_, '_> {
+    type Value = PropertyData;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled property")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: MapAccess<'de>
+    {
+        self.context.within("property", |context| {
+            let mut property = Property::default();
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "name" => {
+                        map.save_value(context, &mut property.name, &key)?;
+                        context.annotate("name", &property.name);
+                    }
+                    "type" => map.save_value(context, &mut property.property_type, &key)?,
+                    "propertytype" => map.save_value(context, &mut property.custom_type, &key)?,
+                    "value" | "$value" => map.save_value(context, &mut property.value, &key)?,
+                    "properties" => map.save_properties(context, &mut property.properties, &key)?,
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            let name = property.name.clone();
+            let value = match property.parse_value() {
+                Ok(mut value) => {
+                    context.resolve_property(&name, &mut value)?;
+                    Some(value)
+                }
+                Err(e) => {
+                    context.report(&name, e)?;
+                    None
+                }
+            };
+
+            Ok(PropertyData { name, value })
+        })
+    }
+}
+
+impl<'de> ContextDeserialize<'de> for PropertyData {
+    #[inline]
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(PropertyVisitor { context })
+    }
+}
+
+/// Contenu d'une balise `<properties>`.
+#[derive(Debug, Default)]
+struct PropertiesData(Properties);
+
+struct PropertiesVisitor<'c, 'a> {
+    context: &'c mut LoadContext<'a>,
+}
+
+impl<'de> Visitor<'de> for PropertiesVisitor<'_, '_> {
+    type Value = PropertiesData;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "tiled properties")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: MapAccess<'de>
+    {
+        self.context.within("properties", |context| {
+            let mut properties = Properties::new();
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "property" => {
+                        let list: Vec<PropertyData> =
+                            map.read_element(context, &key)?.unwrap_or_default();
+
+                        for PropertyData { name, value } in list {
+                            properties.extend(value.map(|value| (name, value)));
+                        }
+                    }
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            Ok(PropertiesData(properties))
+        })
+    }
+}
+
+impl<'de> ContextDeserialize<'de> for PropertiesData {
+    #[inline]
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(PropertiesVisitor { context })
+    }
+}
+
+/// Contenu d'une balise `<tile>` appartenant à un jeu de tuiles.
+#[derive(Debug, Default)]
+struct TileData {
+    /// Identifiant local de la tuile.
+    id: u16,
+    /// Image propre à la tuile.
+    image: Option<Image>,
+    /// Propriétés personnalisées de la tuile.
+    properties: Properties,
+}
+
+struct TileDataVisitor<'c, 'a> {
+    context: &'c mut LoadContext<'a>,
+}
+
+impl<'de> Visitor<'de> for TileDataVisitor<'_, '_> {
+    type Value = TileData;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled tile")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: MapAccess<'de>
+    {
+        self.context.within("tile", |context| {
+            let mut tile = TileData::default();
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "id" => {
+                        map.save_value(context, &mut tile.id, &key)?;
+                        context.annotate("id", &tile.id);
+                    }
+                    "image" => map.save_some(context, &mut tile.image, &key)?,
+                    "properties" => map.save_properties(context, &mut tile.properties, &key)?,
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            Ok(tile)
+        })
+    }
+}
+
+impl<'de> ContextDeserialize<'de> for TileData {
+    #[inline]
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(TileDataVisitor { context })
+    }
+}
+
+struct ImageVisitor<'c, 'a> {
+    context: &'c mut LoadContext<'a>,
+}
+
+impl<'de> Visitor<'de> for ImageVisitor<'_, '_> {
+    type Value = Image;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled image")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: MapAccess<'de>
+    {
+        self.context.within("image", |context| {
+            let mut image = Image::default();
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "source" => map.save_value(context, &mut image.source, &key)?,
+                    "width" => map.save_value(context, &mut image.size.x, &key)?,
+                    "height" => map.save_value(context, &mut image.size.y, &key)?,
+                    "trans" => map.save_value(context, &mut image.trans, &key)?,
+                    "format" => map.save_value(context, &mut image.format, &key)?,
+                    "data" => if let Some(data) = map.read_element::<Data>(context, &key)? {
+                        context.within("data", |context| match data.decode_bytes() {
+                            Ok(bytes) => {
+                                image.data = Some(bytes);
+                                Ok(())
+                            }
+                            Err(e) => context.report_decoding(&key, e),
+                        })?;
+                    }
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            if !image.source.is_empty() {
+                image.path = context.resolve(&image.source);
+            }
+
+            Ok(image)
+        })
+    }
+}
+
+impl<'de> ContextDeserialize<'de> for Image {
+    #[inline]
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(ImageVisitor { context })
+    }
+}
+
+impl<'de> Deserialize<'de> for Image {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        Self::deserialize_in(&mut LoadContext::default(), deserializer)
+    }
+}
+
+struct ObjectVisitor<'c, 'a> {
+    context: &'c mut LoadContext<'a>,
+}
+
+impl<'de> Visitor<'de> for ObjectVisitor<'_, '_> {
+    type Value = Object;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled object")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: MapAccess<'de>
+    {
+        self.context.within("object", |context| {
+            let mut object = Object::default();
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "id" => {
+                        map.save_value(context, &mut object.id, &key)?;
+                        context.annotate("id", &object.id);
+                    }
+                    "gid" => map.save_value(context, &mut object.gid, &key)?,
+                    "template" => map.save_value(context, &mut object.template, &key)?,
+                    "x" => map.save_value(context, &mut object.coords.x, &key)?,
+                    "y" => map.save_value(context, &mut object.coords.y, &key)?,
+                    "width" => map.save_value(context, &mut object.size.x, &key)?,
+                    "height" => map.save_value(context, &mut object.size.y, &key)?,
+                    "properties" => map.save_properties(context, &mut object.properties, &key)?,
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            Ok(object)
+        })
+    }
+}
+
+impl<'de> ContextDeserialize<'de> for Object {
+    #[inline]
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(ObjectVisitor { context })
+    }
+}
+
+impl<'de> Deserialize<'de> for Object {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        Self::deserialize_in(&mut LoadContext::default(), deserializer)
+    }
+}
+
+struct ObjectGroupVisitor<'c, 'a> {
+    context: &'c mut LoadContext<'a>,
+}
+
+impl<'de> Visitor<'de> for ObjectGroupVisitor<'_, '_> {
+    type Value = ObjectGroup;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled object group")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: MapAccess<'de>
+    {
+        self.context.within("objectgroup", |context| {
+            let mut group = ObjectGroup::default();
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "id" => map.save_value(context, &mut group.id, &key)?,
+                    "name" => {
+                        map.save_value(context, &mut group.name, &key)?;
+                        context.annotate("name", &group.name);
+                    }
+                    "object" => map.save_element(context, &mut group.objects, &key)?,
+                    "properties" => map.save_properties(context, &mut group.properties, &key)?,
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            Ok(group)
+        })
+    }
+}
+
+impl<'de> ContextDeserialize<'de> for ObjectGroup {
+    #[inline]
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(ObjectGroupVisitor { context })
+    }
+}
+
+impl<'de> Deserialize<'de> for ObjectGroup {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        Self::deserialize_in(&mut LoadContext::default(), deserializer)
+    }
+}
+
+struct TileSetVisitor<'c, 'a> {
+    context: &'c mut LoadContext<'a>,
+}
+
+impl<'de> Visitor<'de> for TileSetVisitor<'_, '_> {
+    type Value = TileSet;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled tileset")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: MapAccess<'de>
+    {
+        self.context.within("tileset", |context| {
+            let mut tileset = TileSet::default();
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "firstgid" => map.save_value(context, &mut tileset.firstgid, &key)?,
+                    "source" => {
+                        map.save_value(context, &mut tileset.source, &key)?;
+
+                        if let Some(source) = &tileset.source {
+                            context.annotate("source", source);
+                        }
+                    }
+                    "name" => {
+                        map.save_value(context, &mut tileset.name, &key)?;
+                        context.annotate("name", &tileset.name);
+                    }
+                    "tilewidth" => map.save_value(context, &mut tileset.size.x, &key)?,
+                    "tileheight" => map.save_value(context, &mut tileset.size.y, &key)?,
+                    "tilecount" => map.save_value(context, &mut tileset.count, &key)?,
+                    "columns" => map.save_value(context, &mut tileset.columns, &key)?,
+                    "spacing" => map.save_value(context, &mut tileset.spacing, &key)?,
+                    "margin" => map.save_value(context, &mut tileset.margin, &key)?,
+                    "image" if tileset.origin.is_none() => {
+                        if let Some(image) = map.read_element(context, &key)? {
+                            tileset.origin = Some(TilesOrigin::Image(image));
+                        }
+                    }
+                    "image" => {
+                        let _ = map.next_value::<()>();
+                        context.report(&key, already_has_origin(&tileset))?;
+                    }
+                    "tile" => {
+                        let tiles: Option<Vec<TileData>> = map.read_element(context, &key)?;
+                        let mut collection = Vec::new();
+
+                        for tile in tiles.unwrap_or_default() {
+                            if !tile.properties.is_empty() {
+                                tileset.tiles_properties.insert(tile.id, tile.properties);
+                            }
+
+                            if let Some(image) = tile.image {
+                                collection.push(Tile::new(tile.id, image));
+                            }
+                        }
+
+                        match tileset.origin {
+                            _ if collection.is_empty() => (),
+                            None => {
+                                tileset.origin = Some(TilesOrigin::new_collection_from(collection));
+                            }
+                            Some(_) => context.report(&key, already_has_origin(&tileset))?,
+                        }
+                    }
+                    "properties" => map.save_properties(context, &mut tileset.properties, &key)?,
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            if let Some(source) = tileset.source.clone() {
+                let external = context.load_resource(&source, PhantomData::<TileSet>)?;
+
+                if let Some((external, path)) = external {
+                    tileset = TileSet {
+                        firstgid: tileset.firstgid,
+                        source: Some(source),
+                        path: Some(path),
+                        ..external
+                    };
+                }
+            }
+
+            Ok(tileset)
+        })
+    }
+}
+
+/// Décrit l'image ignorée d'un jeu de tuiles possédant déjà une origine.
+fn already_has_origin(tileset: &TileSet) -> String {
+    format!("the tileset \"{}\" already has an origin", tileset.name)
+}
+
+impl<'de> ContextDeserialize<'de> for TileSet {
+    #[inline]
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(TileSetVisitor { context })
+    }
+}
+
+impl<'de> Deserialize<'de> for TileSet {
+    #[inline]
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        Self::deserialize_in(&mut LoadContext::default(), deserializer)
+    }
+}
+
+/// Contenu d'un fichier `.tx`, décrivant le modèle d'un objet.
+#[derive(Debug, Default)]
+struct Template {
+    /// Jeu de tuiles auquel appartient la tuile de l'objet.
+    tileset: Option<TileSet>,
+    /// Objet servant de modèle.
+    object: Object,
+}
+
+struct TemplateVisitor<'c, 'a> {
+    context: &'c mut LoadContext<'a>,
+}
+
+impl<'de> Visitor<'de> for TemplateVisitor<'_, '_> {
+    type Value = Template;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled object template")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where A: MapAccess<'de>
+    {
+        self.context.within("template", |context| {
+            let mut template = Template::default();
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "tileset" => map.save_some(context, &mut template.tileset, &key)?,
+                    "object" => map.save_element(context, &mut template.object, &key)?,
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            Ok(template)
+        })
+    }
+}
+
+impl<'de> ContextDeserialize<'de> for Template {
+    #[inline]
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(TemplateVisitor { context })
+    }
+}
+
+/// Complète les valeurs non renseignées de l'objet par celles de son modèle,
+/// si celui-ci peut être chargé.
+///
+/// La tuile du modèle est convertie en identifiant global de la map à l'aide du
+/// jeu de tuiles partageant le même fichier que celui du modèle. Les propriétés
+/// du modèle sont reprises sauf si l'objet les redéfinit.
+fn apply_template<E: de::Error>(
+    context: &mut LoadContext<'_>,
+    tilesets: &[TileSet],
+    object: &mut Object,
+) -> Result<(), E> {
+    let source = match &object.template {
+        Some(source) => source.clone(),
+        None => return Ok(()),
+    };
+
+    let loaded = context.load_resource(&source, PhantomData)?;
+    let Template { tileset, object: template } = match loaded {
+        Some((template, _)) => template,
+        None => return Ok(()),
+    };
+
+    if let (EMPTY_TILE, Some(gid)) = (object.gid, template.valid_gid()) {
+        let map_gid = tileset
+            .filter(|tileset| tileset.path.is_some())
+            .and_then(|tileset| {
+                let local_id = gid.checked_sub(tileset.firstgid)?;
+                let map_tileset = tilesets.iter().find(|other| other.path == tileset.path)?;
+                map_tileset.firstgid.checked_add(local_id)
+            });
+
+        match map_gid {
+            Some(gid) => object.gid = gid,
+            None => {
+                let message = format!("the tileset of \"{}\" is not in the map", source);
+                context.report("template", message)?;
+            }
+        }
+    }
+
+    if object.size.x == 0 && object.size.y == 0 {
+        object.size = template.size;
+    }
+
+    let mut properties = template.properties;
+    properties.append(&mut object.properties);
+    object.properties = properties;
+
+    Ok(())
+}
+
+/// Balises enfants de `<map>` avant lesquelles les attributs de la map sont
+/// transmis au gestionnaire d'événements.
+const MAP_CHILDREN: [&str; 4] = ["tileset", "layer", "objectgroup", "properties"];
+
+/// Lit une balise `<map>` en transmettant son contenu au gestionnaire
+/// d'événements, et renvoie ses attributs.
+pub(crate) struct MapSeed<'a> {
+    handler: &'a mut dyn MapHandler,
+    /// Indique si le gestionnaire ne conserve que les tuiles situées dans la map.
+    clipped: bool,
+}
+
+impl<'a> MapSeed<'a> {
+    /// Crée une graine transmettant le contenu de la map au gestionnaire passé en
+    /// paramètre.
+    #[inline]
+    pub(crate) fn new(handler: &'a mut dyn MapHandler) -> Self {
+        Self { handler, clipped: false }
+    }
+
+    /// Crée une graine transmettant le contenu de la map à un gestionnaire ne
+    /// conservant que les tuiles situées dans la map : les tuiles des morceaux
+    /// d'une map infinie dépassant de celle-ci sont signalées.
+    #[inline]
+    pub(crate) fn clipped(handler: &'a mut dyn MapHandler) -> Self {
+        Self { handler, clipped: true }
+    }
+}
+
+impl<'de> Visitor<'de> for InContext<'_, '_, MapSeed<'_>> {
+    type Value = MapInfo;
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled map")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<MapInfo, A::Error>
+    where A: MapAccess<'de>
+    {
+        let MapSeed { handler, clipped } = self.seed;
+
+        self.context.within("map", |context| {
+            let mut info = MapInfo::default();
+            let mut tilesets = Vec::new();
+            let mut started = false;
+
+            while let Some(key) = map.next_key::<String>()? {
+                if !started && MAP_CHILDREN.contains(&key.as_str()) {
+                    started = true;
+                    handler.header(&info);
+                }
+
+                match key.as_str() {
+                    "orientation" => map.save_value(context, &mut info.orientation, &key)?,
+                    "width" => map.save_value(context, &mut info.size.x, &key)?,
+                    "height" => map.save_value(context, &mut info.size.y, &key)?,
+                    "tilewidth" => map.save_value(context, &mut info.tile_size.x, &key)?,
+                    "tileheight" => map.save_value(context, &mut info.tile_size.y, &key)?,
+                    "staggeraxis" => map.save_value(context, &mut info.stagger_axis, &key)?,
+                    "staggerindex" => map.save_value(context, &mut info.stagger_index, &key)?,
+                    "hexsidelength" => map.save_value(context, &mut info.hex_side_length, &key)?,
+                    "infinite" => map.save_value(context, &mut info.infinite, &key)?,
+                    "tileset" => {
+                        let read: Option<Vec<TileSet>> = map.read_element(context, &key)?;
+
+                        for tileset in read.unwrap_or_default() {
+                            tilesets.push(tileset.clone());
+                            handler.tileset(tileset);
+                        }
+                        tilesets.sort_by_key(|tileset| tileset.firstgid);
+                    }
+                    "layer" | "objectgroup" if !handler.read_content() => {
+                        let _ = map.next_value::<()>();
+                    }
+                    "layer" => {
+                        let bounds = Some(info.size).filter(|_| clipped && info.infinite);
+                        let tilesets = &tilesets;
+                        let seed = LayerSeed { handler: &mut *handler, tilesets, bounds };
+                        map.read_value_seed(context, &key, seed)?;
+                    }
+                    "objectgroup" => {
+                        let seed = ObjectGroupsSeed { handler: &mut *handler, tilesets: &tilesets };
+                        map.read_value_seed(context, &key, seed)?;
+                    }
+                    "properties" => {
+                        if let Some(PropertiesData(properties)) = map.read_element(context, &key)? {
+                            for (name, value) in properties {
+                                handler.property(name, value);
+                            }
+                        }
+                    }
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            if !started {
+                handler.header(&info);
+            }
+
+            for overlap in data::tileset_overlaps(&tilesets) {
+                context.report("tileset", overlap)?;
+            }
+
+            Ok(info)
+        })
+    }
+}
+
+impl<'de> ContextSeed<'de> for MapSeed<'_> {
+    type Value = MapInfo;
 
-use crate::data::{Image, Map, Object, Tile, TileSet, TilesOrigin};
+    #[inline]
+    fn deserialize_in<D>(
+        self,
+        context: &mut LoadContext<'_>,
+        deserializer: D,
+    ) -> Result<MapInfo, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(InContext { context, seed: self })
+    }
+}
 
-pub use quick_xml::DeError as TMXError;
+/// Lit une balise `<layer>` en transmettant son contenu au gestionnaire
+/// d'événements.
+struct LayerSeed<'a> {
+    handler: &'a mut dyn MapHandler,
+    /// Jeux de tuiles de la map, auxquels doivent appartenir les tuiles du calque.
+    tilesets: &'a [TileSet],
+    /// Taille de la map, hors de laquelle les tuiles sont signalées.
+    bounds: Option<Vector2<u16>>,
+}
 
-trait MapAccessExt<'de>: MapAccess<'de> {
-    /// Tente d'enregistrer la valeur suivante contenue dans la table dans la
-    /// variable passée en paramètre.
-    ///
-    /// Si la valeur ne peut être convertie dans le type de la variable, un message
-    /// d'erreur est affiché, rappelant la clef associée.
-    fn save_value<F>(&mut self, var: &mut F, key: &str)
-    where F: Deserialize<'de>
+impl<'de> Visitor<'de> for InContext<'_, '_, LayerSeed<'_>> {
+    type Value = ();
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a tiled layer")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
+    where A: MapAccess<'de>
     {
-        match self.next_value::<F>() {
-            Ok(value) => *var = value,
-            Err(e) => eprintln!("Warning: field \"{}\" - {}", key, e),
-        }
+        let LayerSeed { handler, tilesets, bounds } = self.seed;
+
+        self.context.within("layer", |context| {
+            let mut layer = LayerInfo::default();
+            let mut started = false;
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "id" => map.save_value(context, &mut layer.id, &key)?,
+                    "name" => {
+                        map.save_value(context, &mut layer.name, &key)?;
+                        context.annotate("name", &layer.name);
+                    }
+                    "width" => map.save_value(context, &mut layer.size.x, &key)?,
+                    "height" => map.save_value(context, &mut layer.size.y, &key)?,
+                    "data" => {
+                        if !std::mem::replace(&mut started, true) {
+                            handler.layer_start(&layer);
+                        }
+
+                        let seed = DataSeed {
+                            handler: &mut *handler,
+                            tilesets,
+                            bounds,
+                            size: layer.size,
+                        };
+                        map.read_value_seed(context, &key, seed)?;
+                    }
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            if !started {
+                handler.layer_start(&layer);
+            }
+
+            Ok(())
+        })
     }
 }
 
-impl<'de, T> MapAccessExt<'de> for T where T: MapAccess<'de> {}
+impl<'de> ContextSeed<'de> for LayerSeed<'_> {
+    type Value = ();
+
+    #[inline]
+    fn deserialize_in<D>(
+        self,
+        context: &mut LoadContext<'_>,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(InContext { context, seed: self })
+    }
+}
 
-struct ImageVisitor;
+/// Lit une balise `<data>` d'un calque de tuiles en transmettant son contenu,
+/// morceau par morceau, au gestionnaire d'événements.
+struct DataSeed<'a> {
+    handler: &'a mut dyn MapHandler,
+    /// Jeux de tuiles de la map, auxquels doivent appartenir les tuiles du calque.
+    tilesets: &'a [TileSet],
+    /// Taille de la map, hors de laquelle les tuiles sont signalées.
+    bounds: Option<Vector2<u16>>,
+    /// Taille du calque, couvert par son contenu lorsque celui-ci n'est pas
+    /// découpé en morceaux.
+    size: Vector2<u16>,
+}
 
-impl<'de> Visitor<'de> for ImageVisitor {
-    type Value = Image;
+impl<'de> Visitor<'de> for InContext<'_, '_, DataSeed<'_>> {
+    type Value = ();
 
     #[inline]
     fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
-        write!(formatter, "a tiled image")
+        write!(formatter, "a tiled layer data")
     }
 
-    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
     where A: MapAccess<'de>
     {
-        let mut image = Image::default();
+        let DataSeed { handler, tilesets, bounds, size } = self.seed;
+
+        let tiles = self.context.within("data", |context| {
+            let mut data = Data::default();
+            let mut chunked = false;
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "encoding" => map.save_value(context, &mut data.encoding, &key)?,
+                    "compression" => map.save_value(context, &mut data.compression, &key)?,
+                    "$value" => map.save_value(context, &mut data.content, &key)?,
+                    "chunk" => {
+                        chunked = true;
+
+                        let seed = ChunksSeed {
+                            handler: &mut *handler,
+                            tilesets,
+                            bounds,
+                            data: &data,
+                        };
+                        map.read_value_seed(context, &key, seed)?;
+                    }
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+                }
+            }
+
+            if chunked {
+                Ok(None)
+            } else {
+                data.decode_tiles(context, &data.content, size)
+            }
+        })?;
+
+        match tiles {
+            Some(tiles) => {
+                let chunk = Chunk { coords: Point2 { x: 0, y: 0 }, size, tiles };
+                send_chunk(self.context, handler, tilesets, bounds, chunk)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<(), E>
+    where E: de::Error
+    {
+        let DataSeed { handler, tilesets, bounds, size } = self.seed;
+        let tiles = self.context.within("data", |context| {
+            Data::default().decode_tiles(context, v, size)
+        })?;
 
-        while let Some(key) = map.next_key::<String>()? {
-            match key.as_str() {
-                "source" => map.save_value(&mut image.source, &key),
-                "width" => map.save_value(&mut image.size.x, &key),
-                "height" => map.save_value(&mut image.size.y, &key),
-                _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+        match tiles {
+            Some(tiles) => {
+                let chunk = Chunk { coords: Point2 { x: 0, y: 0 }, size, tiles };
+                send_chunk(self.context, handler, tilesets, bounds, chunk)
             }
+            None => Ok(()),
         }
-        
-        Ok(image)
     }
 }
 
-impl<'de> Deserialize<'de> for Image {
+impl<'de> ContextSeed<'de> for DataSeed<'_> {
+    type Value = ();
+
     #[inline]
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    fn deserialize_in<D>(
+        self,
+        context: &mut LoadContext<'_>,
+        deserializer: D,
+    ) -> Result<(), D::Error>
     where D: Deserializer<'de>
     {
-        deserializer.deserialize_map(ImageVisitor)
+        deserializer.deserialize_map(InContext { context, seed: self })
     }
 }
 
-struct ObjectVisitor;
+/// Lit les balises `<chunk>` consécutives d'une balise `<data>`.
+struct ChunksSeed<'a> {
+    handler: &'a mut dyn MapHandler,
+    /// Jeux de tuiles de la map, auxquels doivent appartenir les tuiles du calque.
+    tilesets: &'a [TileSet],
+    /// Taille de la map, hors de laquelle les tuiles sont signalées.
+    bounds: Option<Vector2<u16>>,
+    /// Balise `<data>` contenant les morceaux, dont l'encodage et l'algorithme de
+    /// compression s'appliquent à chacun d'eux.
+    data: &'a Data,
+}
 
-impl<'de> Visitor<'de> for ObjectVisitor {
-    type Value = Object;
+impl<'de> Visitor<'de> for InContext<'_, '_, ChunksSeed<'_>> {
+    type Value = ();
 
     #[inline]
     fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
-        write!(formatter, "a tiled object")
+        write!(formatter, "a sequence of tiled chunks")
     }
 
-    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
-    where A: MapAccess<'de>
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where A: SeqAccess<'de>
     {
-        let mut object = Object::default();
-
-        while let Some(key) = map.next_key::<String>()? {
-            match key.as_str() {
-                "id" => map.save_value(&mut object.id, &key),
-                "gid" => map.save_value(&mut object.gid, &key),
-                "x" => map.save_value(&mut object.coords.x, &key),
-                "y" => map.save_value(&mut object.coords.y, &key),
-                "width" => map.save_value(&mut object.size.x, &key),
-                "height" => map.save_value(&mut object.size.y, &key),
-                _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
+        let ChunksSeed { handler, tilesets, bounds, data } = self.seed;
+        let context = self.context;
+
+        while let Some(chunk) = seq.next_element_seed(InContext {
+            context: &mut *context,
+            seed: ChunkSeed { data },
+        })? {
+            if let Some(chunk) = chunk {
+                send_chunk(context, &mut *handler, tilesets, bounds, chunk)?;
             }
         }
 
-        Ok(object)
+        Ok(())
     }
 }
 
-impl<'de> Deserialize<'de> for Object {
+impl<'de> ContextSeed<'de> for ChunksSeed<'_> {
+    type Value = ();
+
     #[inline]
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    fn deserialize_in<D>(
+        self,
+        context: &mut LoadContext<'_>,
+        deserializer: D,
+    ) -> Result<(), D::Error>
     where D: Deserializer<'de>
     {
-        deserializer.deserialize_map(ObjectVisitor)
+        deserializer.deserialize_seq(InContext { context, seed: self })
     }
 }
 
-struct TileSetVisitor;
+/// Lit une balise `<chunk>`, et renvoie son contenu s'il a pu être décodé.
+struct ChunkSeed<'a> {
+    /// Balise `<data>` contenant le morceau.
+    data: &'a Data,
+}
 
-impl<'de> Visitor<'de> for TileSetVisitor {
-    type Value = TileSet;
+impl<'de> Visitor<'de> for InContext<'_, '_, ChunkSeed<'_>> {
+    type Value = Option<Chunk>;
 
     #[inline]
     fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
-        write!(formatter, "a tiled tileset")
+        write!(formatter, "a tiled chunk")
     }
 
-    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    fn visit_map<A>(self, mut map: A) -> Result<Option<Chunk>, A::Error>
     where A: MapAccess<'de>
     {
-        let mut tileset = TileSet::default();
-
-        while let Some(key) = map.next_key::<String>()? {
-            match key.as_str() {
-                "firstgid" => map.save_value(&mut tileset.firstgid, &key),
-                "name" => map.save_value(&mut tileset.name, &key),
-                "tilewidth" => map.save_value(&mut tileset.size.x, &key),
-                "tileheight" => map.save_value(&mut tileset.size.y, &key),
-                "tilecount" => map.save_value(&mut tileset.count, &key),
-                "columns" => map.save_value(&mut tileset.columns, &key),
-                "image" if tileset.origin.is_none() => {
-                    if let Ok(image) = map.next_value::<Image>() {
-                        tileset.origin = Some(TilesOrigin::Image(image));
-                    }
-                }
-                "tile" if tileset.origin.is_none() => {
-                    if let Ok(tiles) = map.next_value::<Vec<Tile>>() {
-                        tileset.origin = Some(TilesOrigin::new_collection_from(tiles));
-                    }
-                }
-                "image" | "tile" => {
-                    println!("Warning: the tileset \"{}\" as already an origin", tileset.name);
+        let data = self.seed.data;
+
+        self.context.within("chunk", |context| {
+            let mut coords = Point2 { x: 0, y: 0 };
+            let mut size = Vector2 { x: 0, y: 0 };
+            let mut content = String::new();
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "x" => map.save_value(context, &mut coords.x, &key)?,
+                    "y" => map.save_value(context, &mut coords.y, &key)?,
+                    "width" => map.save_value(context, &mut size.x, &key)?,
+                    "height" => map.save_value(context, &mut size.y, &key)?,
+                    "$value" => map.save_value(context, &mut content, &key)?,
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
                 }
-                _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
             }
+
+            let tiles = data.decode_tiles(context, &content, size)?;
+            Ok(tiles.map(|tiles| Chunk { coords, size, tiles }))
+        })
+    }
+}
+
+impl<'de> ContextSeed<'de> for ChunkSeed<'_> {
+    type Value = Option<Chunk>;
+
+    #[inline]
+    fn deserialize_in<D>(
+        self,
+        context: &mut LoadContext<'_>,
+        deserializer: D,
+    ) -> Result<Option<Chunk>, D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(InContext { context, seed: self })
+    }
+}
+
+/// Signale les identifiants globaux invalides du morceau passé en paramètre, et
+/// ses tuiles situées hors des limites éventuelles de la map, puis transmet
+/// celui-ci au gestionnaire d'événements.
+fn send_chunk<E>(
+    context: &mut LoadContext<'_>,
+    handler: &mut dyn MapHandler,
+    tilesets: &[TileSet],
+    bounds: Option<Vector2<u16>>,
+    chunk: Chunk,
+) -> Result<(), E>
+where E: de::Error
+{
+    check_gids(context, tilesets, chunk.tiles.iter().copied(), "data")?;
+
+    if let Some(bounds) = bounds {
+        let outside = outside_tiles(&chunk, bounds);
+
+        if outside > 0 {
+            let message = format!(
+                "{} tiles of the chunk at ({}, {}) lie outside the map and are dropped",
+                outside, chunk.coords.x, chunk.coords.y,
+            );
+            context.report("chunk", message)?;
         }
+    }
+
+    handler.layer_chunk(chunk);
+    Ok(())
+}
+
+/// Compte les tuiles non vides du morceau passé en paramètre situées hors d'une
+/// map de la taille passée en paramètre.
+pub(crate) fn outside_tiles(chunk: &Chunk, bounds: Vector2<u16>) -> usize {
+    let width = usize::from(chunk.size.x).max(1);
+    let inside = |start: i32, offset: usize, size: u16| {
+        let coord = i64::from(start) + offset as i64;
+        (0..i64::from(size)).contains(&coord)
+    };
+
+    chunk.tiles
+        .iter()
+        .enumerate()
+        .filter(|&(_, &gid)| gid != EMPTY_TILE)
+        .filter(|&(index, _)| {
+            !inside(chunk.coords.x, index % width, bounds.x)
+                || !inside(chunk.coords.y, index / width, bounds.y)
+        })
+        .count()
+}
+
+/// Lit les balises `<objectgroup>` consécutives d'une map en transmettant leur
+/// contenu au gestionnaire d'événements.
+struct ObjectGroupsSeed<'a> {
+    handler: &'a mut dyn MapHandler,
+    /// Jeux de tuiles de la map, auxquels doivent appartenir les tuiles des objets.
+    tilesets: &'a [TileSet],
+}
 
-        Ok(tileset)
+impl<'de> Visitor<'de> for InContext<'_, '_, ObjectGroupsSeed<'_>> {
+    type Value = ();
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a sequence of tiled object groups")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where A: SeqAccess<'de>
+    {
+        let ObjectGroupsSeed { handler, tilesets } = self.seed;
+        let context = self.context;
+
+        loop {
+            let seed = ObjectGroupSeed { handler: &mut *handler, tilesets };
+
+            if seq.next_element_seed(InContext { context: &mut *context, seed })?.is_none() {
+                return Ok(());
+            }
+        }
     }
 }
 
-impl<'de> Deserialize<'de> for TileSet {
+impl<'de> ContextSeed<'de> for ObjectGroupsSeed<'_> {
+    type Value = ();
+
     #[inline]
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    fn deserialize_in<D>(
+        self,
+        context: &mut LoadContext<'_>,
+        deserializer: D,
+    ) -> Result<(), D::Error>
     where D: Deserializer<'de>
     {
-        deserializer.deserialize_map(TileSetVisitor)
+        deserializer.deserialize_seq(InContext { context, seed: self })
     }
 }
 
-struct MapVisitor;
+/// Lit une balise `<objectgroup>` en transmettant ses attributs, ses objets, puis
+/// le groupe lui-même, au gestionnaire d'événements.
+struct ObjectGroupSeed<'a> {
+    handler: &'a mut dyn MapHandler,
+    /// Jeux de tuiles de la map, auxquels doivent appartenir les tuiles des objets.
+    tilesets: &'a [TileSet],
+}
 
-impl<'de> Visitor<'de> for MapVisitor {
-    type Value = Map;
+impl<'de> Visitor<'de> for InContext<'_, '_, ObjectGroupSeed<'_>> {
+    type Value = ();
 
     #[inline]
     fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
-        write!(formatter, "a tiled map")
+        write!(formatter, "a tiled object group")
     }
 
-    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    fn visit_map<A>(self, mut map: A) -> Result<(), A::Error>
     where A: MapAccess<'de>
     {
-        let mut tmx_map = Map::default();
-
-        while let Some(key) = map.next_key::<String>()? {
-            match key.as_str() {
-                "orientation" => map.save_value(&mut tmx_map.orientation, &key),
-                "width" => map.save_value(&mut tmx_map.size.x, &key),
-                "height" => map.save_value(&mut tmx_map.size.y, &key),
-                "tilewidth" => map.save_value(&mut tmx_map.tile_size.x, &key),
-                "tileheight" => map.save_value(&mut tmx_map.tile_size.y, &key),
-                "staggeraxis" => map.save_value(&mut tmx_map.stagger_axis, &key),
-                "tileset" => map.save_value(&mut tmx_map.tilesets, &key),
-                "objectgroup" => map.save_value(&mut tmx_map.object_groups, &key),
-                "layer" => if let Ok(layer) = map.next_value::<HashMap<String, String>>() {
-                    if let Some(encoded_data) = layer.get("data") {
-                        tmx_map.tiles = decode_csv_data(encoded_data);
+        let ObjectGroupSeed { handler, tilesets } = self.seed;
+
+        self.context.within("objectgroup", |context| {
+            let mut info = ObjectGroupInfo::default();
+            let mut properties = Properties::new();
+            let mut started = false;
+
+            while let Some(key) = map.next_key::<String>()? {
+                match key.as_str() {
+                    "id" => map.save_value(context, &mut info.id, &key)?,
+                    "name" => {
+                        map.save_value(context, &mut info.name, &key)?;
+                        context.annotate("name", &info.name);
+                    }
+                    "object" => {
+                        if !std::mem::replace(&mut started, true) {
+                            handler.object_group_start(&info);
+                        }
+
+                        let seed = ObjectsSeed { handler: &mut *handler, tilesets };
+                        map.read_value_seed(context, &key, seed)?;
                     }
+                    "properties" => map.save_properties(context, &mut properties, &key)?,
+                    _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
                 }
-                _ => { let _ = map.next_value::<()>(); } // Passe à la valeur suivante
             }
+
+            if !started {
+                handler.object_group_start(&info);
+            }
+
+            let ObjectGroupInfo { id, name } = info;
+            handler.object_group(ObjectGroup { id, name, objects: Vec::new(), properties });
+            Ok(())
+        })
+    }
+}
+
+impl<'de> ContextSeed<'de> for ObjectGroupSeed<'_> {
+    type Value = ();
+
+    #[inline]
+    fn deserialize_in<D>(
+        self,
+        context: &mut LoadContext<'_>,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_map(InContext { context, seed: self })
+    }
+}
+
+/// Lit les balises `<object>` consécutives d'un groupe d'objets, et transmet
+/// chacun d'eux au gestionnaire d'événements une fois son modèle appliqué.
+struct ObjectsSeed<'a> {
+    handler: &'a mut dyn MapHandler,
+    /// Jeux de tuiles de la map, auxquels doivent appartenir les tuiles des objets.
+    tilesets: &'a [TileSet],
+}
+
+impl<'de> Visitor<'de> for InContext<'_, '_, ObjectsSeed<'_>> {
+    type Value = ();
+
+    #[inline]
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "a sequence of tiled objects")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<(), A::Error>
+    where A: SeqAccess<'de>
+    {
+        let ObjectsSeed { handler, tilesets } = self.seed;
+        let context = self.context;
+
+        while let Some(mut object) = seq.next_element_seed(InContext {
+            context: &mut *context,
+            seed: PhantomData::<Object>,
+        })? {
+            context.within("object", |context| {
+                context.annotate("id", &object.id);
+                apply_template(context, tilesets, &mut object)?;
+                check_gids(context, tilesets, object.valid_gid(), "gid")
+            })?;
+
+            handler.object(object);
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de> ContextSeed<'de> for ObjectsSeed<'_> {
+    type Value = ();
+
+    #[inline]
+    fn deserialize_in<D>(
+        self,
+        context: &mut LoadContext<'_>,
+        deserializer: D,
+    ) -> Result<(), D::Error>
+    where D: Deserializer<'de>
+    {
+        deserializer.deserialize_seq(InContext { context, seed: self })
+    }
+}
+
+/// Signale chaque identifiant global distinct n'appartenant à aucun des jeux de
+/// tuiles passés en paramètre, triés par `firstgid`.
+fn check_gids<E, I>(
+    context: &mut LoadContext<'_>,
+    tilesets: &[TileSet],
+    gids: I,
+    field: &str,
+) -> Result<(), E>
+where E: de::Error, I: IntoIterator<Item = u16>
+{
+    let mut invalid = BTreeSet::new();
+
+    for gid in gids.into_iter().filter(|&gid| gid != EMPTY_TILE) {
+        if Map::gid_owner(tilesets, gid).is_none() && invalid.insert(gid) {
+            context.report_invalid_gid(field, u32::from(gid))?;
         }
+    }
 
-        tmx_map.reorder_tilesets();
+    Ok(())
+}
 
-        Ok(tmx_map)
+impl<'de> ContextDeserialize<'de> for Map {
+    fn deserialize_in<D>(context: &mut LoadContext<'_>, deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de>
+    {
+        let mut builder = MapBuilder::default();
+        let info = MapSeed::clipped(&mut builder).deserialize_in(context, deserializer)?;
+        Ok(builder.build(info))
     }
 }
 
@@ -201,27 +1639,118 @@ impl<'de> Deserialize<'de> for Map {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where D: Deserializer<'de>
     {
-        deserializer.deserialize_map(MapVisitor)
+        Self::deserialize_in(&mut LoadContext::default(), deserializer)
     }
 }
 
 /// Décode le texte contenu au format csv dans la balise `<data>` en une liste
-/// d'identifiants globaux de tuiles.
-fn decode_csv_data(data: &str) -> Vec<u16> {
+/// d'identifiants globaux de tuiles, sans leurs bits de symétrie et de rotation.
+///
+/// Chaque valeur invalide est transmise à la fonction passée en paramètre, qui
+/// peut interrompre le décodage ; sinon, elle est remplacée par une tuile vide
+/// afin que les tuiles suivantes conservent leur position.
+fn decode_csv_data<E, F>(data: &str, mut invalid: F) -> Result<Vec<u16>, E>
+where F: FnMut(ParsingError) -> Result<(), E>
+{
+    let mut tiles = Vec::new();
+
+    for value in data.split([',', '\n']).map(str::trim).filter(|value| !value.is_empty()) {
+        match value.parse().ok().and_then(data::tile_gid) {
+            Some(gid) => tiles.push(gid),
+            None => {
+                invalid(ParsingError::InvalidString(String::from(value)))?;
+                tiles.push(EMPTY_TILE);
+            }
+        }
+    }
+
+    Ok(tiles)
+}
+
+/// Décode le contenu d'un calque de tuiles en une liste d'identifiants globaux,
+/// selon l'encodage et l'algorithme de compression spécifiés.
+pub(crate) fn decode_tiles_data(
+    data: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u16>, ParsingError> {
+    match encoding {
+        None | Some("csv") => decode_csv_data(data, Err),
+        Some("base64") => {
+            let compression = match compression {
+                None | Some("") => None,
+                Some(compression) => Some(compression.parse()?),
+            };
+
+            let bytes = decompress_data(decode_base64_data(data)?, compression)?;
+            decode_binary_data(&bytes)
+        }
+        Some(encoding) => Err(ParsingError::InvalidString(String::from(encoding))),
+    }
+}
+
+/// Décompresse les données selon l'algorithme de compression spécifié.
+fn decompress_data(
+    data: Vec<u8>,
+    compression: Option<Compression>,
+) -> Result<Vec<u8>, ParsingError> {
+    let mut decompressed = Vec::new();
+
+    let result = match compression {
+        None => return Ok(data),
+        Some(Compression::Zlib) => ZlibDecoder::new(data.as_slice()).read_to_end(&mut decompressed),
+        Some(Compression::Gzip) => GzDecoder::new(data.as_slice()).read_to_end(&mut decompressed),
+        #[cfg(feature = "zstd")]
+        Some(Compression::Zstd) => {
+            zstd::stream::copy_decode(data.as_slice(), &mut decompressed).map(|_| 0)
+        }
+        #[cfg(not(feature = "zstd"))]
+        Some(Compression::Zstd) => return Err(ParsingError::InvalidString(String::from("zstd"))),
+    };
+
+    result
+        .map(|_| decompressed)
+        .map_err(|e| ParsingError::InvalidString(e.to_string()))
+}
+
+/// Décode des données binaires en une liste d'identifiants globaux, chacun étant
+/// stocké sur 4 octets au format petit-boutiste avec ses bits de symétrie et de
+/// rotation.
+fn decode_binary_data(data: &[u8]) -> Result<Vec<u16>, ParsingError> {
+    if data.len() % 4 != 0 {
+        return Err(ParsingError::InvalidString(format!("{} bytes of tiles data", data.len())));
+    }
+
     data
-        .split(|c: char| c == ',' || c == '\n')
-        .filter_map(|d| d.trim().parse().ok())
+        .chunks_exact(4)
+        .map(|bytes| {
+            let gid = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            data::tile_gid(gid).ok_or_else(|| ParsingError::InvalidString(gid.to_string()))
+        })
         .collect()
 }
 
+/// Décode le texte contenu au format base64 dans la balise `<data>`.
+fn decode_base64_data(data: &str) -> Result<Vec<u8>, ParsingError> {
+    let data: String = data.split_whitespace().collect();
+
+    if data.is_empty() {
+        return Err(ParsingError::EmptyString);
+    }
+
+    base64::decode(&data).map_err(|_| ParsingError::InvalidString(data))
+}
+
 #[cfg(test)]
 mod tests {
     use mint::{Point2, Vector2};
     use serde_test::{assert_de_tokens, Token};
 
-    use crate::data::{Image, Map, Object, ObjectGroup, Orientation, StaggerAxis,
-                      Tile, TileSet, TilesOrigin};
-    use super::decode_csv_data;
+    use crate::data::{Color, EMPTY_TILE, Image, LoadOptions, Map, Object, ObjectGroup,
+                      Orientation, ParsingError, Properties, StaggerAxis, StaggerIndex, Tile,
+                      TileSet, TilesOrigin};
+    use super::{decode_base64_data, decode_csv_data, decode_tiles_data};
+    use crate::error::TMXError;
 
     #[test]
     fn test_decode_csv_data() {
@@ -231,15 +1760,81 @@ mod tests {
 
         let should_be = vec! [0, 0, 0, 3, 2, 1];
 
-        let tiles = decode_csv_data(src);
-        assert_eq!(tiles, should_be);
+        let tiles = decode_csv_data::<ParsingError, _>(src, Err);
+        assert_eq!(tiles, Ok(should_be));
+
+        let mut invalid = Vec::new();
+        let tiles = decode_csv_data::<ParsingError, _>("1,x,\n2147483649,65536", |e| {
+            invalid.push(e);
+            Ok(())
+        });
+
+        assert_eq!(tiles, Ok(vec![1, EMPTY_TILE, 1, EMPTY_TILE]));
+        assert_eq!(invalid, [
+            ParsingError::InvalidString(String::from("x")),
+            ParsingError::InvalidString(String::from("65536")),
+        ]);
+        assert_eq!(decode_csv_data::<ParsingError, _>("1073741826,536870915", Err), Ok(vec![2, 3]));
+        assert!(decode_csv_data::<ParsingError, _>("1,x", Err).is_err());
+    }
+
+    #[test]
+    fn test_de_color() {
+        assert_de_tokens(&Color::rgba(255, 0, 255, 128), &[Token::Str("#80ff00ff")]);
+    }
+
+    #[test]
+    fn test_decode_base64_data() {
+        assert_eq!(decode_base64_data(" YmFy\n "), Ok(b"bar".to_vec()));
+        assert!(decode_base64_data("Y*Fy").is_err());
+        assert!(decode_base64_data("").is_err());
+    }
+
+    #[test]
+    fn test_decode_tiles_data() {
+        let should_be = vec![0, 0, 0, 3, 2, 1];
+
+        let csv = decode_tiles_data("0,0,0,\n3,2,1", Some("csv"), None);
+        assert_eq!(csv, Ok(should_be.clone()));
+
+        let base64 = decode_tiles_data("AAAAAAAAAAAAAAAAAwAAAAIAAAABAAAA", Some("base64"), None);
+        assert_eq!(base64, Ok(should_be.clone()));
+
+        let zlib = decode_tiles_data("eJxjYEAAZiBmAmJGIAYAAFAABw==", Some("base64"), Some("zlib"));
+        assert_eq!(zlib, Ok(should_be.clone()));
+
+        let gzip = decode_tiles_data(
+            "H4sIAAAAAAACA2NgQABmIGYCYkYgBgDIeMYuGAAAAA==",
+            Some("base64"),
+            Some("gzip")
+        );
+        assert_eq!(gzip, Ok(should_be));
+
+        let flipped = decode_tiles_data("AQAAgAIAAEADAAAg", Some("base64"), None);
+        assert_eq!(flipped, Ok(vec![1, 2, 3]));
+        assert!(decode_tiles_data("AAABAA==", Some("base64"), None).is_err());
+
+        assert!(decode_tiles_data("AAAAAAAAAAAAAAAAAwAAAAIAAAABAAAA", Some("base64"), Some("zlib")).is_err());
+        assert!(decode_tiles_data("AAAAAAAAAAAAAAAAAwAAAAIAAAABAA==", Some("base64"), None).is_err());
+        assert!(decode_tiles_data("AAAAAAAAAAAAAAAAAwAAAAIAAAABAAAA", Some("xml"), None).is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_decode_zstd_tiles_data() {
+        let bytes: Vec<u8> = [0u32, 3, 2, 1].iter().flat_map(|gid| gid.to_le_bytes()).collect();
+        let compressed = zstd::encode_all(bytes.as_slice(), 0).unwrap();
+
+        let tiles = decode_tiles_data(&base64::encode(compressed), Some("base64"), Some("zstd"));
+        assert_eq!(tiles, Ok(vec![0, 3, 2, 1]));
     }
 
     #[test]
     fn test_de_image() {
-        let image = Image {
+        let mut image = Image {
             source: "foo".to_string(),
             size: Vector2 { x: 48, y: 24 },
+            ..Default::default()
         };
 
         assert_de_tokens(&image, &[
@@ -252,6 +1847,58 @@ mod tests {
             Token::U16(24),
             Token::StructEnd
         ]);
+
+        image.trans = Some(Color::rgb(255, 0, 255));
+        image.format = Some("png".to_string());
+        image.data = Some(b"bar".to_vec());
+
+        assert_de_tokens(&image, &[
+            Token::Struct { name: "image", len: 6 },
+            Token::Str("source"),
+            Token::String("foo"),
+            Token::Str("width"),
+            Token::U16(48),
+            Token::Str("height"),
+            Token::U16(24),
+            Token::Str("trans"),
+            Token::Some,
+            Token::Str("ff00ff"),
+            Token::Str("format"),
+            Token::Some,
+            Token::String("png"),
+            Token::Str("data"),
+            Token::Map { len: Some(2) },
+            Token::Str("encoding"),
+            Token::Some,
+            Token::String("base64"),
+            Token::Str("$value"),
+            Token::String("YmFy"),
+            Token::MapEnd,
+            Token::StructEnd
+        ]);
+    }
+
+    #[test]
+    fn test_load_embedded_image() {
+        let src = r#"
+        <image format="png" trans="ff00ff" width="48" height="24">
+            <data encoding="base64">
+                YmFy
+            </data>
+        </image>"#;
+
+        let should_be = Image {
+            source: String::new(),
+            path: None,
+            size: Vector2 { x: 48, y: 24 },
+            trans: Some(Color::rgb(255, 0, 255)),
+            format: Some("png".to_string()),
+            data: Some(b"bar".to_vec()),
+        };
+
+        let image: Image = quick_xml::de::from_str(src).unwrap();
+        assert_eq!(image, should_be);
+        assert!(image.is_embedded());
     }
 
     #[test]
@@ -259,6 +1906,7 @@ mod tests {
         let image = Image {
             source: "foo".to_string(),
             size: Vector2 { x: 48, y: 24 },
+            ..Default::default()
         };
 
         let tile = Tile {
@@ -291,7 +1939,8 @@ mod tests {
             count: 4,
             columns: 2,
             name: "foo".to_string(),
-            origin: None
+            origin: None,
+            ..Default::default()
         };
 
         let tokens = vec![
@@ -316,6 +1965,7 @@ mod tests {
         let image = Image {
             source: "bar".to_string(),
             size: Vector2 { x: 24, y: 24 },
+            ..Default::default()
         };
 
         tileset.origin = Some(TilesOrigin::Image(image.clone()));
@@ -392,7 +2042,9 @@ mod tests {
             id: 0,
             gid: 1,
             coords: Point2 { x: 10, y: 20 },
-            size: Vector2 { x: 24, y: 12 }
+            size: Vector2 { x: 24, y: 12 },
+            properties: Properties::new(),
+            template: None,
         };
 
         assert_de_tokens(&object, &[
@@ -422,8 +2074,11 @@ mod tests {
                 id: 0,
                 gid: 1,
                 coords: Point2 { x: 10, y: 20 },
-                size: Vector2 { x: 24, y: 12 }
-            }]
+                size: Vector2 { x: 24, y: 12 },
+                properties: Properties::new(),
+                template: None,
+            }],
+            properties: Properties::new(),
         };
 
         assert_de_tokens(&object_group, &[
@@ -457,13 +2112,16 @@ mod tests {
     fn test_de_map() {
         let map = Map {
             tilesets: vec![],
-            tileset_indexes: vec![None],
             size: Vector2 { x: 10, y: 10 },
             tile_size: Vector2 { x: 24, y: 12},
             tiles: vec![0, 0, 0, 3, 2, 1],
             object_groups: vec![],
             orientation: Orientation::Isometric,
-            stagger_axis: StaggerAxis::XAxis
+            stagger_axis: StaggerAxis::XAxis,
+            stagger_index: StaggerIndex::Odd,
+            hex_side_length: 0,
+            properties: Properties::new(),
+            base_dir: None,
         };
 
         assert_de_tokens(&map, &[
@@ -498,4 +2156,145 @@ mod tests {
             Token::StructEnd
         ]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_load_tmx_strict_and_lenient() {
+        let strict = LoadOptions { strict: true, ..LoadOptions::default() };
+        let src = r#"
+        <map orientation="orthogonal" width="3x" height="2" tilewidth="24" tileheight="24">
+            <tileset firstgid="1" name="foo" tilewidth="24" tileheight="24" tilecount="4" columns="2">
+                <image source="foo.png" width="48" height="48"/>
+                <tile id="0">
+                    <image source="a.png" width="24" height="24"/>
+                </tile>
+            </tileset>
+            <properties>
+                <property name="int" type="int" value="foo"/>
+                <property name="bool" type="bool" value="true"/>
+            </properties>
+            <layer id="1" width="3" height="2">
+                <data encoding="base64">A*A</data>
+            </layer>
+        </map>"#;
+
+        let (map, warnings) = Map::load_tmx_with(src.as_bytes(), &LoadOptions::default()).unwrap();
+        let fields: Vec<&str> = warnings.iter().map(|warning| warning.field.as_str()).collect();
+
+        assert_eq!(fields, vec!["width", "tile", "int", "data"]);
+        assert_eq!(map.size, Vector2 { x: 0, y: 2 });
+        assert_eq!(map.properties.len(), 1);
+        assert_eq!(map.tilesets().len(), 1);
+        assert_eq!(Map::load_tmx(src).unwrap(), map);
+
+        let error = Map::load_tmx_with(src.as_bytes(), &strict).unwrap_err();
+        assert!(error.to_string().contains("field \"width\""), "{}", error);
+
+        let nested = src
+            .replace("width=\"3x\"", "width=\"3\"")
+            .replace("id=\"0\"", "id=\"a\"");
+        let error = Map::load_tmx_with(nested.as_bytes(), &strict).unwrap_err();
+        assert!(error.to_string().contains("field \"id\""), "{}", error);
+        assert!(!error.to_string().contains("field \"tileset\""), "{}", error);
+
+        let valid = r#"<map orientation="orthogonal" width="1" height="1" tilewidth="2"/>"#;
+        assert!(Map::load_tmx_with(valid.as_bytes(), &strict).is_ok());
+    }
+
+    #[test]
+    fn test_load_tmx_error_location() {
+        let strict = LoadOptions { strict: true, ..LoadOptions::default() };
+        let src = r#"<map orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8">
+ <tileset firstgid="1" name="foo" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+  <tile id="1">
+   <properties>
+    <property name="speed" type="float" value="fast"/>
+   </properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="Ground" width="2" height="1">
+  <data encoding="csv">1,7</data>
+ </layer>
+ <objectgroup id="2" name="Items">
+  <object id="3" gid="9" x="0" y="0"/>
+ </objectgroup>
+</map>"#;
+
+        let (_, warnings) = Map::load_tmx_with(src.as_bytes(), &LoadOptions::default()).unwrap();
+        let paths: Vec<&str> = warnings
+            .iter()
+            .map(|warning| warning.location.path.as_str())
+            .collect();
+
+        assert_eq!(paths, vec![
+            "map/tileset[name=foo]/tile[id=1]/properties/property[name=speed]",
+            "map/layer[name=Ground]",
+            "map/objectgroup[name=Items]/object[id=3]",
+        ]);
+        assert!(warnings.iter().all(|warning| warning.location.line > 1), "{:?}", warnings);
+
+        match Map::load_tmx_with(src.as_bytes(), &strict).unwrap_err() {
+            TMXError::Attribute { location, name, .. } => {
+                assert_eq!(name, "speed");
+                assert_eq!(location.line, 5);
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+
+        let invalid_gid = src.replace("\"fast\"", "\"1.5\"");
+        match Map::load_tmx_with(invalid_gid.as_bytes(), &strict).unwrap_err() {
+            TMXError::InvalidGid { location, gid } => {
+                assert_eq!(gid, 7);
+                assert_eq!(location.path, "map/layer[name=Ground]");
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+
+        let decoding = src.replace("encoding=\"csv\">1,7", "encoding=\"base64\">A*A");
+        let lenient = LoadOptions::default();
+        let (_, warnings) = Map::load_tmx_with(decoding.as_bytes(), &lenient).unwrap();
+        assert_eq!(warnings[1].location.path, "map/layer[name=Ground]/data");
+
+        let error = Map::load_tmx_with(
+            decoding.replace("\"fast\"", "\"1.5\"").as_bytes(),
+            &strict,
+        ).unwrap_err();
+        assert!(matches!(error, TMXError::Decoding { .. }), "{}", error);
+        let message = error.to_string();
+        assert!(message.starts_with("map/layer[name=Ground]/data (line 10"), "{}", message);
+
+        let error = Map::load_tmx_with("<map><layer></map>".as_bytes(), &strict).unwrap_err();
+        assert!(matches!(error, TMXError::Syntax { .. }), "{}", error);
+    }
+
+    #[test]
+    fn test_load_csv_data() {
+        let strict = LoadOptions { strict: true, ..LoadOptions::default() };
+        let src = r#"<map orientation="orthogonal" width="3" height="1" tilewidth="8">
+            <tileset firstgid="1" name="foo" tilewidth="8" tileheight="8" tilecount="2"/>
+            <layer id="1" name="Ground" width="3" height="1">
+                <data encoding="csv">2147483649,x,65536</data>
+            </layer>
+        </map>"#;
+
+        let error = Map::load_tmx_with(src.as_bytes(), &strict).unwrap_err();
+        assert!(matches!(error, TMXError::Decoding { .. }), "{}", error);
+
+        let lenient = LoadOptions::default();
+        let (map, warnings) = Map::load_tmx_with(src.as_bytes(), &lenient).unwrap();
+        assert_eq!(map.tiles, vec![1, EMPTY_TILE, EMPTY_TILE]);
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings
+            .iter()
+            .all(|warning| warning.location.path == "map/layer[name=Ground]/data"));
+
+        // Les tuiles retournées ou pivotées sont lues comme leur tuile de base.
+        let flipped = src.replace("x,65536", "1073741826,2");
+        let (map, warnings) = Map::load_tmx_with(flipped.as_bytes(), &strict).unwrap();
+        assert_eq!(map.tiles, vec![1, 2, 2]);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        let short = src.replace("2147483649,x,65536", "1,2");
+        let error = Map::load_tmx_with(short.as_bytes(), &strict).unwrap_err();
+        assert!(error.to_string().contains("expected 3 tiles, found 2"), "{}", error);
+    }
+}
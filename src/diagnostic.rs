@@ -0,0 +1,520 @@
+use std::cell::Cell;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+
+use quick_xml::DeError;
+use serde::Deserializer;
+use serde::de;
+
+use crate::data::PropertyValue;
+use crate::error::{Location, TMXError};
+use crate::project::Project;
+use crate::resource::ResourceReader;
+
+/// Avertissement émis lors du chargement permissif d'un fichier, lorsqu'une
+/// valeur invalide a été ignorée.
+///
+/// Avec la fonctionnalité `log`, chaque avertissement est également transmis au
+/// journal au niveau `warn`, et l'erreur faisant échouer un chargement au niveau
+/// `error`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Warning {
+    /// Emplacement de l'élément concerné.
+    pub location: Location,
+    /// Attribut ou balise dont la valeur a été ignorée.
+    pub field: String,
+    /// Description du problème rencontré.
+    pub message: String,
+}
+
+impl Warning {
+    /// Crée un nouvel avertissement.
+    #[inline]
+    fn new(location: Location, field: &str, message: String) -> Self {
+        Self { location, field: String::from(field), message }
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: field \"{}\" - {}", self.location, self.field, self.message)
+    }
+}
+
+/// Ligne et colonne de lecture, partagées entre le lecteur et le contexte.
+type SharedPosition = Rc<Cell<(usize, usize)>>;
+
+/// Lecteur mettant à jour la ligne et la colonne de lecture au fur et à mesure
+/// que les données sont consommées.
+struct PositionReader<R> {
+    inner: R,
+    position: SharedPosition,
+}
+
+impl<R> PositionReader<R> {
+    /// Avance la position de lecture après les octets passés en paramètre.
+    fn advance(position: &SharedPosition, bytes: &[u8]) {
+        let (mut line, mut column) = position.get();
+
+        for &byte in bytes {
+            if byte == b'\n' {
+                line += 1;
+                column = 1;
+            } else if byte & 0xC0 != 0x80 {
+                // Les octets de continuation UTF-8 n'ajoutent pas de caractère.
+                column += 1;
+            }
+        }
+
+        position.set((line, column));
+    }
+}
+
+impl<R: BufRead> Read for PositionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = self.inner.read(buf)?;
+        Self::advance(&self.position, &buf[..count]);
+        Ok(count)
+    }
+}
+
+impl<R: BufRead> BufRead for PositionReader<R> {
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        if let Ok(buf) = self.inner.fill_buf() {
+            Self::advance(&self.position, &buf[..amt.min(buf.len())]);
+        }
+
+        self.inner.consume(amt);
+    }
+}
+
+/// Fichiers accessibles depuis un fichier en cours de chargement.
+#[derive(Clone, Copy)]
+pub(crate) struct Resources<'a> {
+    /// Dossier contenant le fichier, par rapport auquel sont résolus les chemins
+    /// d'accès qu'il contient.
+    pub(crate) dir: &'a Path,
+    /// Source des fichiers référencés.
+    pub(crate) reader: &'a dyn ResourceReader,
+}
+
+impl Resources<'_> {
+    /// Résout le chemin d'accès passé en paramètre par rapport au dossier du
+    /// fichier.
+    #[inline]
+    pub(crate) fn resolve(&self, source: &str) -> PathBuf {
+        join(self.dir, source)
+    }
+}
+
+/// Graine lisant une valeur à l'aide du contexte du chargement en cours, qui lui
+/// est transmis au moment de la lecture.
+pub(crate) trait ContextSeed<'de>: Sized {
+    /// Valeur produite par la graine.
+    type Value;
+
+    /// Lit la valeur depuis le désérialiseur passé en paramètre, en signalant les
+    /// problèmes rencontrés au contexte.
+    fn deserialize_in<D>(
+        self,
+        context: &mut LoadContext<'_>,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error>
+    where D: Deserializer<'de>;
+}
+
+/// Charge un fichier `.tmx` à l'aide de la graine passée en paramètre, en mode
+/// strict ou permissif, et renvoie son résultat ainsi que les avertissements
+/// émis.
+///
+/// Les fichiers référencés ne sont chargés que si les fichiers accessibles
+/// depuis celui-ci sont connus, et les propriétés ne sont résolues que si le
+/// projet est connu.
+pub(crate) fn load_tmx<S, R>(
+    reader: R,
+    strict: bool,
+    resources: Option<Resources<'_>>,
+    project: Option<&Project>,
+    seed: S,
+) -> (Result<S::Value, TMXError>, Vec<Warning>)
+where S: ContextSeed<'static>, R: BufRead
+{
+    let mut context = LoadContext::new(strict, resources, project);
+    let result = context.load(reader, seed);
+    (result, context.warnings)
+}
+
+/// État d'un chargement en cours, transmis à chacune des graines qui y
+/// participent.
+pub(crate) struct LoadContext<'a> {
+    /// Indique si les valeurs invalides font échouer le chargement.
+    strict: bool,
+    /// Fichiers accessibles depuis le fichier chargé.
+    resources: Option<Resources<'a>>,
+    /// Projet dont les types personnalisés servent à résoudre les propriétés.
+    project: Option<&'a Project>,
+    /// Position de lecture dans le fichier.
+    position: SharedPosition,
+    /// Balises en cours de lecture, complétées par leur nom ou identifiant.
+    path: Vec<String>,
+    /// Emplacement de la balise la plus profonde dont la lecture a échoué.
+    failure: Option<Location>,
+    /// Champ dont la lecture a fait échouer le chargement en mode strict.
+    field: Option<String>,
+    /// Erreur ayant fait échouer le chargement, quel que soit son mode.
+    error: Option<TMXError>,
+    /// Avertissements émis en mode permissif.
+    warnings: Vec<Warning>,
+}
+
+impl<'a> LoadContext<'a> {
+    /// Crée le contexte d'un nouveau chargement.
+    pub(crate) fn new(
+        strict: bool,
+        resources: Option<Resources<'a>>,
+        project: Option<&'a Project>,
+    ) -> Self {
+        Self {
+            strict,
+            resources,
+            project,
+            position: Rc::new(Cell::new((1, 1))),
+            path: Vec::new(),
+            failure: None,
+            field: None,
+            error: None,
+            warnings: Vec::new(),
+        }
+    }
+
+    /// Lit le fichier passé en paramètre à l'aide de la graine, en situant les
+    /// problèmes rencontrés dans celui-ci.
+    fn load<S, R>(&mut self, reader: R, seed: S) -> Result<S::Value, TMXError>
+    where S: ContextSeed<'static>, R: BufRead
+    {
+        let reader = PositionReader { inner: reader, position: Rc::clone(&self.position) };
+        let mut deserializer = quick_xml::de::Deserializer::from_reader(reader);
+
+        seed.deserialize_in(self, &mut deserializer).map_err(|e| {
+            let error = self.error.take().unwrap_or_else(|| self.convert(e));
+            log_error(&error);
+            error
+        })
+    }
+
+    /// Renvoie l'emplacement de la balise en cours de lecture.
+    fn location(&self) -> Location {
+        let (line, column) = self.position.get();
+        Location { path: self.path.join("/"), line, column }
+    }
+
+    /// Convertit une erreur de désérialisation n'ayant pas été signalée par le
+    /// chargement.
+    fn convert(&mut self, error: DeError) -> TMXError {
+        let location = self.failure.take().unwrap_or_else(|| self.location());
+
+        match (error, self.field.take()) {
+            (DeError::Xml(quick_xml::Error::Io(e)), _) => TMXError::Io(e),
+            (DeError::Xml(e), _) => TMXError::Syntax { location, message: e.to_string() },
+            (e, Some(name)) => TMXError::Attribute { location, name, message: e.to_string() },
+            (e, None) => TMXError::Syntax { location, message: e.to_string() },
+        }
+    }
+
+    /// Résout le chemin d'accès passé en paramètre par rapport au dossier du
+    /// fichier en cours de chargement, si celui-ci est connu.
+    pub(crate) fn resolve(&self, source: &str) -> Option<PathBuf> {
+        self.resources.map(|resources| resources.resolve(source))
+    }
+
+    /// Charge à l'aide de la graine passée en paramètre le fichier référencé par
+    /// un chemin d'accès, et renvoie son contenu ainsi que son chemin résolu.
+    ///
+    /// Rien n'est chargé si les fichiers accessibles depuis le fichier en cours de
+    /// chargement ne sont pas connus. Le fichier référencé est chargé dans le même
+    /// mode que celui-ci, et ses avertissements lui sont ajoutés. Un fichier
+    /// introuvable ou mal formé fait échouer le chargement, même en mode permissif.
+    pub(crate) fn load_resource<S, E>(
+        &mut self,
+        source: &str,
+        seed: S,
+    ) -> Result<Option<(S::Value, PathBuf)>, E>
+    where S: ContextSeed<'static>, E: de::Error
+    {
+        let resources = match self.resources {
+            Some(resources) => resources,
+            None => return Ok(None),
+        };
+
+        let path = resources.resolve(source);
+        let file = match resources.reader.open(&path) {
+            Ok(file) => file,
+            Err(error) => {
+                let message = format!("cannot read \"{}\" - {}", source, error);
+                return Err(self.fail(message, |location| {
+                    TMXError::MissingResource { location, source: String::from(source), error }
+                }));
+            }
+        };
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let resources = Resources { dir, reader: resources.reader };
+        let mut context = LoadContext::new(self.strict, Some(resources), self.project);
+        let result = context.load(BufReader::new(file), seed);
+        self.warnings.append(&mut context.warnings);
+
+        match result {
+            Ok(value) => Ok(Some((value, path))),
+            Err(error) => Err(self.fail(error.to_string(), |_| error)),
+        }
+    }
+
+    /// Fait échouer le chargement, quel que soit son mode, avec l'erreur
+    /// construite par la fonction passée en paramètre.
+    fn fail<E, B>(&mut self, message: String, build: B) -> E
+    where E: de::Error, B: FnOnce(Location) -> TMXError
+    {
+        let location = self.location();
+        self.error.get_or_insert_with(|| build(location));
+
+        E::custom(message)
+    }
+
+    /// Lit le contenu d'une balise à l'aide de la fonction passée en paramètre,
+    /// en ajoutant celle-ci au chemin courant le temps de sa lecture.
+    ///
+    /// Si la lecture échoue, l'emplacement de la balise la plus profonde est
+    /// conservé afin de situer l'erreur.
+    pub(crate) fn within<T, E, F>(&mut self, name: &str, read: F) -> Result<T, E>
+    where F: FnOnce(&mut Self) -> Result<T, E>
+    {
+        self.path.push(String::from(name));
+        let result = read(self);
+
+        if result.is_err() && self.failure.is_none() {
+            self.failure = Some(self.location());
+        }
+
+        self.path.pop();
+        result
+    }
+
+    /// Complète le nom de la balise en cours de lecture par la valeur d'un de ses
+    /// attributs, par exemple `layer[name=Ground]`.
+    pub(crate) fn annotate(&mut self, attribute: &str, value: &dyn fmt::Display) {
+        if let Some(element) = self.path.last_mut() {
+            if let Some(start) = element.find('[') {
+                element.truncate(start);
+            }
+
+            element.push_str(&format!("[{}={}]", attribute, value));
+        }
+    }
+
+    /// Résout la valeur d'une propriété à l'aide des types personnalisés du
+    /// projet, s'il est connu.
+    ///
+    /// Une valeur ne correspondant pas aux types du projet fait échouer le
+    /// chargement, quel que soit son mode.
+    pub(crate) fn resolve_property<E>(
+        &mut self,
+        name: &str,
+        value: &mut PropertyValue,
+    ) -> Result<(), E>
+    where E: de::Error
+    {
+        match self.project.map(|project| project.resolve_property(value)) {
+            Some(Err(e)) => Err(self.fail(e.to_string(), |location| {
+                TMXError::Attribute { location, name: String::from(name), message: e.to_string() }
+            })),
+            _ => Ok(()),
+        }
+    }
+
+    /// Signale un problème, en construisant l'erreur associée à l'aide de la
+    /// fonction passée en paramètre.
+    ///
+    /// En mode strict, une erreur est renvoyée afin d'interrompre le chargement ;
+    /// sinon, un avertissement est enregistré et le chargement continue.
+    fn report_with<E, B>(&mut self, field: &str, message: String, build: B) -> Result<(), E>
+    where E: de::Error, B: FnOnce(Location, String) -> TMXError
+    {
+        let location = self.location();
+
+        if self.strict {
+            self.error.get_or_insert_with(|| build(location, message.clone()));
+            Err(E::custom(&message))
+        } else {
+            self.warn(Warning::new(location, field, message));
+            Ok(())
+        }
+    }
+
+    /// Enregistre un avertissement, en le transmettant au journal.
+    fn warn(&mut self, warning: Warning) {
+        log_warning(&warning);
+        self.warnings.push(warning);
+    }
+
+    /// Signale la valeur invalide d'un attribut ou du contenu d'une balise.
+    pub(crate) fn report<E, M>(&mut self, field: &str, message: M) -> Result<(), E>
+    where E: de::Error, M: fmt::Display
+    {
+        self.report_with(field, message.to_string(), |location, message| {
+            TMXError::Attribute { location, name: String::from(field), message }
+        })
+    }
+
+    /// Signale le contenu d'une balise ne pouvant être décodé.
+    pub(crate) fn report_decoding<E, M>(&mut self, field: &str, message: M) -> Result<(), E>
+    where E: de::Error, M: fmt::Display
+    {
+        self.report_with(field, message.to_string(), |location, message| {
+            TMXError::Decoding { location, message }
+        })
+    }
+
+    /// Signale un identifiant global n'appartenant à aucun jeu de tuiles.
+    pub(crate) fn report_invalid_gid<E>(&mut self, field: &str, gid: u32) -> Result<(), E>
+    where E: de::Error
+    {
+        let message = format!("gid {} does not belong to any tileset", gid);
+        self.report_with(field, message, |location, _| TMXError::InvalidGid { location, gid })
+    }
+
+    /// Signale l'erreur obtenue lors de la lecture du champ passé en paramètre.
+    ///
+    /// En mode strict, l'erreur est propagée en retenant le champ concerné. En
+    /// mode permissif, un avertissement situé dans la balise la plus profonde
+    /// ayant échoué est enregistré, sauf si le chargement doit échouer quel que
+    /// soit son mode.
+    pub(crate) fn report_error<E: de::Error>(&mut self, field: &str, error: E) -> Result<(), E> {
+        if self.error.is_some() {
+            return Err(error);
+        }
+
+        if self.strict {
+            if self.field.is_none() {
+                self.field = Some(String::from(field));
+            }
+
+            return Err(error);
+        }
+
+        let location = self.failure.take().unwrap_or_else(|| self.location());
+        self.warn(Warning::new(location, field, error.to_string()));
+
+        Ok(())
+    }
+}
+
+/// Contexte d'une valeur lue directement à l'aide de serde : comme avec
+/// [`Map::load_tmx`](crate::data::Map::load_tmx), les valeurs invalides sont
+/// ignorées, et aucun fichier référencé n'est accessible.
+impl Default for LoadContext<'_> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(false, None, None)
+    }
+}
+
+/// Transmet un avertissement au journal lorsque la fonctionnalité `log` est
+/// activée ; sinon, l'avertissement n'est pas affiché.
+#[inline]
+fn log_warning(warning: &Warning) {
+    #[cfg(feature = "log")]
+    log::warn!("{}", warning);
+    #[cfg(not(feature = "log"))]
+    let _ = warning;
+}
+
+/// Transmet au journal l'erreur ayant fait échouer un chargement lorsque la
+/// fonctionnalité `log` est activée.
+#[inline]
+fn log_error(error: &TMXError) {
+    #[cfg(feature = "log")]
+    log::error!("{}", error);
+    #[cfg(not(feature = "log"))]
+    let _ = error;
+}
+
+/// Joint un chemin d'accès relatif au dossier passé en paramètre, en retirant
+/// les composantes `.` et `..` superflues, afin qu'un même fichier référencé
+/// depuis deux dossiers différents possède le même chemin résolu.
+fn join(dir: &Path, source: &str) -> PathBuf {
+    let mut path = PathBuf::new();
+
+    for component in dir.join(source).components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir if matches!(
+                path.components().next_back(),
+                Some(Component::Normal(_))
+            ) => {
+                path.pop();
+            }
+            component => path.push(component),
+        }
+    }
+
+    path
+}
+
+#[cfg(all(test, feature = "log"))]
+mod tests {
+    use std::sync::Mutex;
+
+    use log::{Level, Log, Metadata, Record};
+
+    use crate::data::{LoadOptions, Map};
+
+    /// Journal conservant les messages reçus.
+    struct Recorder(Mutex<Vec<(Level, String)>>);
+
+    impl Log for Recorder {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            let message = record.args().to_string();
+            self.0.lock().unwrap().push((record.level(), message));
+        }
+
+        fn flush(&self) {}
+    }
+
+    #[test]
+    fn test_log_diagnostics() {
+        let recorder: &'static Recorder = Box::leak(Box::new(Recorder(Mutex::new(Vec::new()))));
+        log::set_logger(recorder).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let src = r#"<map orientation="orthogonal" width="2" height="1" tilewidth="8">
+            <layer id="1" name="Logged" width="2" height="1">
+                <data encoding="base64">A*A</data>
+            </layer>
+        </map>"#;
+
+        let strict = LoadOptions { strict: true, ..LoadOptions::default() };
+        Map::load_tmx_with(src.as_bytes(), &LoadOptions::default()).unwrap();
+        Map::load_tmx_with(src.as_bytes(), &strict).unwrap_err();
+
+        let records = recorder.0.lock().unwrap();
+        let records: Vec<&(Level, String)> = records
+            .iter()
+            .filter(|(_, message)| message.contains("layer[name=Logged]/data (line 3"))
+            .collect();
+
+        assert_eq!(records.len(), 2, "{:?}", records);
+        assert_eq!(records[0].0, Level::Warn);
+        assert_eq!(records[1].0, Level::Error);
+    }
+}
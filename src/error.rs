@@ -0,0 +1,148 @@
+use std::fmt;
+use std::io;
+
+/// Emplacement d'un élément dans un fichier `.tmx`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Location {
+    /// Chemin de l'élément depuis la racine du fichier, chaque balise pouvant être
+    /// complétée par son nom ou son identifiant, par exemple
+    /// `map/layer[name=Ground]/data`.
+    pub path: String,
+    /// Ligne du fichier, à partir de 1, ou 0 si elle est inconnue.
+    pub line: usize,
+    /// Colonne de la ligne, à partir de 1, ou 0 si elle est inconnue.
+    ///
+    /// La ligne et la colonne correspondent à la position de lecture lorsque le
+    /// problème a été détecté, soit généralement la fin de la balise ouvrante de
+    /// l'élément concerné.
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "<root>")?;
+        } else {
+            write!(f, "{}", self.path)?;
+        }
+
+        if self.line != 0 {
+            write!(f, " (line {}, column {})", self.line, self.column)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Représente les erreurs possibles lors de la lecture ou de l'écriture d'un
+/// fichier `.tmx`.
+#[derive(Debug)]
+pub enum TMXError {
+    /// Le document XML est mal formé, ou sa structure ne correspond pas au format.
+    Syntax {
+        /// Emplacement de l'erreur.
+        location: Location,
+        /// Description de l'erreur.
+        message: String,
+    },
+    /// La valeur d'un attribut, ou le contenu d'une balise, ne peut être converti.
+    Attribute {
+        /// Emplacement de l'élément possédant l'attribut.
+        location: Location,
+        /// Nom de l'attribut ou de la balise.
+        name: String,
+        /// Description de l'erreur.
+        message: String,
+    },
+    /// Le contenu d'une balise `<data>` ne peut être décodé ou décompressé.
+    Decoding {
+        /// Emplacement de la balise.
+        location: Location,
+        /// Description de l'erreur.
+        message: String,
+    },
+    /// Un fichier référencé par la map, comme un jeu de tuiles externe, est
+    /// introuvable ou illisible.
+    MissingResource {
+        /// Emplacement de l'élément référençant le fichier.
+        location: Location,
+        /// Chemin d'accès vers le fichier.
+        source: String,
+        /// Erreur obtenue lors de la lecture du fichier.
+        error: io::Error,
+    },
+    /// Un identifiant global n'appartient à aucun jeu de tuiles de la map.
+    InvalidGid {
+        /// Emplacement de l'élément utilisant l'identifiant.
+        location: Location,
+        /// Identifiant global invalide.
+        gid: u32,
+    },
+    /// Erreur de lecture ou d'écriture.
+    Io(io::Error),
+}
+
+impl TMXError {
+    /// Renvoie l'emplacement de l'erreur, s'il est connu.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            Self::Syntax { location, .. }
+            | Self::Attribute { location, .. }
+            | Self::Decoding { location, .. }
+            | Self::MissingResource { location, .. }
+            | Self::InvalidGid { location, .. } => Some(location),
+            Self::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for TMXError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax { location, message } => write!(f, "{}: {}", location, message),
+            Self::Attribute { location, name, message } => {
+                write!(f, "{}: field \"{}\" - {}", location, name, message)
+            }
+            Self::Decoding { location, message } => {
+                write!(f, "{}: cannot decode data - {}", location, message)
+            }
+            Self::MissingResource { location, source, error } => {
+                write!(f, "{}: cannot read \"{}\" - {}", location, source, error)
+            }
+            Self::InvalidGid { location, gid } => {
+                write!(f, "{}: gid {} does not belong to any tileset", location, gid)
+            }
+            Self::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for TMXError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::MissingResource { error, .. } | Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for TMXError {
+    #[inline]
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// Convertit une erreur obtenue lors de l'écriture d'un fichier `.tmx`.
+///
+/// Les erreurs de lecture étant situées par le chargement lui-même, une erreur
+/// convertie ne possède pas d'emplacement dans un document : il s'agit d'une
+/// erreur d'écriture.
+impl From<quick_xml::Error> for TMXError {
+    fn from(error: quick_xml::Error) -> Self {
+        match error {
+            quick_xml::Error::Io(e) => Self::Io(e),
+            e => Self::Io(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
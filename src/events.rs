@@ -0,0 +1,535 @@
+use std::convert::TryFrom;
+use std::io::BufRead;
+
+use mint::{Point2, Vector2};
+
+use crate::data::{EMPTY_TILE, LoadOptions, Map, Object, ObjectGroup, Orientation, Properties,
+                  PropertyValue, StaggerAxis, StaggerIndex, TileSet};
+use crate::deserial::MapSeed;
+use crate::diagnostic::{self, Warning};
+use crate::error::TMXError;
+
+/// Attributs de la balise `<map>`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MapInfo {
+    /// Taille de la map.
+    pub size: Vector2<u16>,
+    /// Taille en pixels des tuiles composant la map.
+    pub tile_size: Vector2<u16>,
+    /// Orientation de la map.
+    pub orientation: Orientation,
+    /// Axe de décalage de la map.
+    pub stagger_axis: StaggerAxis,
+    /// Lignes ou colonnes décalées de la map.
+    pub stagger_index: StaggerIndex,
+    /// Longueur en pixels du côté plat des tuiles d'une map hexagonale.
+    pub hex_side_length: u16,
+    /// Indique si les calques de la map sont découpés en morceaux de taille
+    /// variable plutôt que limités à la taille de la map.
+    pub infinite: bool,
+}
+
+impl Default for MapInfo {
+    fn default() -> Self {
+        Self {
+            size: Vector2 { x: 0, y: 0 },
+            tile_size: Vector2 { x: 0, y: 0 },
+            orientation: Orientation::Orthogonal,
+            stagger_axis: StaggerAxis::None,
+            stagger_index: StaggerIndex::Odd,
+            hex_side_length: 0,
+            infinite: false,
+        }
+    }
+}
+
+/// Attributs d'une balise `<layer>`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LayerInfo {
+    /// Identifiant unique du calque.
+    pub id: u16,
+    /// Nom du calque.
+    pub name: String,
+    /// Taille du calque.
+    pub size: Vector2<u16>,
+}
+
+impl Default for LayerInfo {
+    fn default() -> Self {
+        Self { id: 0, name: String::new(), size: Vector2 { x: 0, y: 0 } }
+    }
+}
+
+/// Attributs d'une balise `<objectgroup>`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ObjectGroupInfo {
+    /// Identifiant unique du calque.
+    pub id: u16,
+    /// Nom du groupe d'objets.
+    pub name: String,
+}
+
+/// Morceau rectangulaire du contenu d'un calque de tuiles.
+///
+/// Le contenu d'un calque d'une map finie forme un seul morceau couvrant tout le
+/// calque, tandis que celui d'une map infinie est découpé en plusieurs morceaux.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Chunk {
+    /// Coordonnées du coin supérieur gauche du morceau, en tuiles.
+    pub coords: Point2<i32>,
+    /// Taille du morceau, en tuiles.
+    pub size: Vector2<u16>,
+    /// Identifiants globaux des tuiles du morceau, ligne par ligne.
+    pub tiles: Vec<u16>,
+}
+
+/// Reçoit le contenu d'une map au fur et à mesure de sa lecture par
+/// [`visit_tmx`], sans que la map ne soit construite en mémoire.
+///
+/// Chaque méthode ne fait rien par défaut, afin de n'implémenter que celles
+/// dont les données sont utiles.
+pub trait MapHandler {
+    /// Appelée une fois les attributs de la map lus, avant tout autre événement.
+    fn header(&mut self, _info: &MapInfo) {}
+
+    /// Appelée pour chaque jeu de tuiles, une fois celui-ci entièrement lu.
+    fn tileset(&mut self, _tileset: TileSet) {}
+
+    /// Appelée au début de chaque calque de tuiles, avant ses morceaux.
+    fn layer_start(&mut self, _layer: &LayerInfo) {}
+
+    /// Appelée pour chaque morceau du calque de tuiles en cours de lecture.
+    fn layer_chunk(&mut self, _chunk: Chunk) {}
+
+    /// Appelée au début de chaque groupe d'objets, avant ses objets.
+    fn object_group_start(&mut self, _group: &ObjectGroupInfo) {}
+
+    /// Appelée pour chaque objet du groupe d'objets en cours de lecture, après
+    /// que son modèle éventuel a été appliqué.
+    fn object(&mut self, _object: Object) {}
+
+    /// Appelée à la fin de chaque groupe d'objets, après ses objets.
+    ///
+    /// La liste des objets du groupe est vide, ceux-ci ayant déjà été transmis à
+    /// [`MapHandler::object`].
+    fn object_group(&mut self, _group: ObjectGroup) {}
+
+    /// Appelée pour chaque propriété personnalisée de la map.
+    fn property(&mut self, _name: String, _value: PropertyValue) {}
+
+    /// Indique si les calques de tuiles et les groupes d'objets doivent être lus.
+    ///
+    /// Dans le cas contraire, ces balises sont ignorées sans que leur contenu ne
+    /// soit décodé, et seuls les événements [`MapHandler::header`],
+    /// [`MapHandler::tileset`] et [`MapHandler::property`] sont reçus.
+    fn read_content(&self) -> bool {
+        true
+    }
+}
+
+/// Lit un fichier `.tmx` depuis le lecteur passé en paramètre en transmettant son
+/// contenu au gestionnaire d'événements passé en paramètre, et renvoie les
+/// avertissements émis.
+///
+/// Équivaut à [`visit_tmx_with`] avec les options par défaut : comme avec
+/// [`Map::load_tmx`], les valeurs invalides sont ignorées.
+///
+/// # Erreurs
+///
+/// Cette fonction retourne une erreur si les données lues ne sont pas au format
+/// XML, ou si la lecture échoue.
+#[inline]
+pub fn visit_tmx<R, H>(reader: R, handler: &mut H) -> Result<Vec<Warning>, TMXError>
+where R: BufRead, H: MapHandler
+{
+    visit_tmx_with(reader, handler, &LoadOptions::default())
+}
+
+/// Lit un fichier `.tmx` depuis le lecteur passé en paramètre en transmettant son
+/// contenu au gestionnaire d'événements passé en paramètre, et renvoie les
+/// avertissements émis.
+///
+/// Les options sont appliquées comme avec [`Map::load_tmx_with`] : les jeux de
+/// tuiles externes et les modèles d'objets sont chargés depuis la source de
+/// fichiers des options avant d'être transmis au gestionnaire.
+///
+/// # Erreurs
+///
+/// Cette fonction retourne une erreur dans les mêmes cas que
+/// [`Map::load_tmx_with`].
+pub fn visit_tmx_with<R, H>(
+    reader: R,
+    handler: &mut H,
+    options: &LoadOptions<'_>,
+) -> Result<Vec<Warning>, TMXError>
+where R: BufRead, H: MapHandler
+{
+    let seed = MapSeed::new(handler);
+    let (result, warnings) = diagnostic::load_tmx(
+        reader, options.strict, options.root_resources(), options.project, seed,
+    );
+    result.map(|_| warnings)
+}
+
+/// Métadonnées d'une map, lues sans le contenu de ses calques ni ses objets.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MapHeader {
+    /// Attributs de la map.
+    pub info: MapInfo,
+    /// Jeux de tuiles référencés par la map. Sans source de fichiers dans les
+    /// options de lecture, les jeux de tuiles externes ne sont pas chargés : seul
+    /// leur fichier source et leur `firstgid` sont renseignés.
+    pub tilesets: Vec<TileSet>,
+    /// Propriétés personnalisées de la map.
+    pub properties: Properties,
+}
+
+impl MapHeader {
+    /// Lit les métadonnées d'une map depuis les données d'un fichier `.tmx`.
+    ///
+    /// Équivaut à [`MapHeader::scan_with`] avec les options par défaut : les
+    /// valeurs invalides sont ignorées, comme avec [`Map::load_tmx`].
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si les données passées en paramètre ne
+    /// sont pas au format XML.
+    #[inline]
+    pub fn scan<D: AsRef<[u8]>>(data: D) -> Result<Self, TMXError> {
+        Self::scan_with(data.as_ref(), &LoadOptions::default()).map(|(header, _)| header)
+    }
+
+    /// Lit les métadonnées d'une map depuis un fichier `.tmx` lu au fur et à
+    /// mesure depuis le lecteur passé en paramètre, et renvoie les avertissements
+    /// émis.
+    ///
+    /// Les balises `<layer>` et `<objectgroup>` sont ignorées sans être décodées,
+    /// ce qui rend cette lecture bien plus rapide que celle de la map entière.
+    /// Les options sont appliquées comme avec [`Map::load_tmx_with`].
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur dans les mêmes cas que
+    /// [`Map::load_tmx_with`], le contenu des calques et des groupes d'objets
+    /// n'étant toutefois pas vérifié.
+    pub fn scan_with<R: BufRead>(
+        reader: R,
+        options: &LoadOptions<'_>,
+    ) -> Result<(Self, Vec<Warning>), TMXError> {
+        let mut header = HeaderBuilder(Self::default());
+        let seed = MapSeed::new(&mut header);
+        let (info, warnings) = diagnostic::load_tmx(
+            reader, options.strict, options.root_resources(), options.project, seed,
+        );
+        let HeaderBuilder(header) = header;
+
+        Ok((Self { info: info?, ..header }, warnings))
+    }
+}
+
+/// Construit les métadonnées d'une map à partir des événements reçus.
+struct HeaderBuilder(MapHeader);
+
+impl MapHandler for HeaderBuilder {
+    fn tileset(&mut self, tileset: TileSet) {
+        self.0.tilesets.push(tileset);
+    }
+
+    fn property(&mut self, name: String, value: PropertyValue) {
+        self.0.properties.insert(name, value);
+    }
+
+    fn read_content(&self) -> bool {
+        false
+    }
+}
+
+/// Construit une map à partir des événements reçus.
+#[derive(Debug, Default)]
+pub(crate) struct MapBuilder {
+    /// Map en cours de construction.
+    map: Map,
+    /// Attributs de la map.
+    info: MapInfo,
+    /// Indique si le contenu du calque précédent doit être remplacé par le
+    /// prochain morceau reçu.
+    new_layer: bool,
+}
+
+impl MapBuilder {
+    /// Termine la construction de la map à partir de ses attributs.
+    pub(crate) fn build(mut self, info: MapInfo) -> Map {
+        self.map.size = info.size;
+        self.map.tile_size = info.tile_size;
+        self.map.orientation = info.orientation;
+        self.map.stagger_axis = info.stagger_axis;
+        self.map.stagger_index = info.stagger_index;
+        self.map.hex_side_length = info.hex_side_length;
+        self.map.reorder_tilesets();
+
+        self.map
+    }
+}
+
+impl MapHandler for MapBuilder {
+    fn header(&mut self, info: &MapInfo) {
+        self.info = info.clone();
+    }
+
+    fn tileset(&mut self, tileset: TileSet) {
+        self.map.tilesets.push(tileset);
+    }
+
+    fn layer_start(&mut self, _layer: &LayerInfo) {
+        self.new_layer = true;
+    }
+
+    /// Seul le contenu du dernier calque est conservé. Dans une map infinie, les
+    /// tuiles situées hors de la taille de la map sont ignorées, après avoir été
+    /// signalées lors de la lecture.
+    fn layer_chunk(&mut self, chunk: Chunk) {
+        if !self.info.infinite {
+            self.map.tiles = chunk.tiles;
+            return;
+        }
+
+        let width = usize::from(self.info.size.x);
+        let height = usize::from(self.info.size.y);
+
+        if std::mem::take(&mut self.new_layer) || self.map.tiles.len() != width * height {
+            self.map.tiles = vec![EMPTY_TILE; width * height];
+        }
+
+        let rows = chunk.tiles.chunks(usize::from(chunk.size.x).max(1));
+
+        for (y, row) in (i64::from(chunk.coords.y)..).zip(rows) {
+            for (x, &gid) in (i64::from(chunk.coords.x)..).zip(row) {
+                if let (Ok(x), Ok(y)) = (usize::try_from(x), usize::try_from(y)) {
+                    if x < width && y < height {
+                        self.map.tiles[y * width + x] = gid;
+                    }
+                }
+            }
+        }
+    }
+
+    fn object_group_start(&mut self, group: &ObjectGroupInfo) {
+        self.map.object_groups.push(ObjectGroup {
+            id: group.id,
+            name: group.name.clone(),
+            ..ObjectGroup::default()
+        });
+    }
+
+    fn object(&mut self, object: Object) {
+        if let Some(group) = self.map.object_groups.last_mut() {
+            group.objects.push(object);
+        }
+    }
+
+    /// Seules les propriétés du groupe restent à conserver, ses attributs et ses
+    /// objets ayant déjà été reçus.
+    fn object_group(&mut self, group: ObjectGroup) {
+        if let Some(last) = self.map.object_groups.last_mut() {
+            last.properties = group.properties;
+        }
+    }
+
+    fn property(&mut self, name: String, value: PropertyValue) {
+        self.map.properties.insert(name, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// Compte les événements reçus.
+    #[derive(Default)]
+    struct Counter {
+        info: MapInfo,
+        tilesets: Vec<String>,
+        layers: Vec<String>,
+        chunks: Vec<(Point2<i32>, usize)>,
+        objects: usize,
+        group_starts: Vec<(String, usize)>,
+        groups: Vec<(String, usize)>,
+        properties: Vec<String>,
+    }
+
+    impl MapHandler for Counter {
+        fn header(&mut self, info: &MapInfo) {
+            self.info = info.clone();
+        }
+
+        fn tileset(&mut self, tileset: TileSet) {
+            self.tilesets.push(tileset.name);
+        }
+
+        fn layer_start(&mut self, layer: &LayerInfo) {
+            self.layers.push(layer.name.clone());
+        }
+
+        fn layer_chunk(&mut self, chunk: Chunk) {
+            self.chunks.push((chunk.coords, chunk.tiles.len()));
+        }
+
+        fn object_group_start(&mut self, group: &ObjectGroupInfo) {
+            self.group_starts.push((group.name.clone(), self.objects));
+        }
+
+        fn object(&mut self, _object: Object) {
+            self.objects += 1;
+        }
+
+        fn object_group(&mut self, group: ObjectGroup) {
+            self.groups.push((group.name, self.objects));
+        }
+
+        fn property(&mut self, name: String, _value: PropertyValue) {
+            self.properties.push(name);
+        }
+    }
+
+    const INFINITE: &str = r#"
+    <map orientation="orthogonal" width="4" height="4" tilewidth="8" tileheight="8" infinite="1">
+        <properties>
+            <property name="spawn" value="north"/>
+        </properties>
+        <tileset firstgid="1" name="terrain" tilewidth="8" tileheight="8" tilecount="4" columns="2"/>
+        <layer id="1" name="Ground" width="4" height="4">
+            <data encoding="csv">
+                <chunk x="-1" y="0" width="2" height="2">1,2,3,4</chunk>
+                <chunk x="2" y="2" width="2" height="2">4,3,2,1</chunk>
+            </data>
+        </layer>
+        <objectgroup id="2" name="Spawns">
+            <object id="1" x="0" y="0"/>
+            <object id="2" x="8" y="8"/>
+        </objectgroup>
+        <objectgroup id="3" name="Items">
+            <object id="3" gid="9" x="0" y="0"/>
+        </objectgroup>
+    </map>"#;
+
+    #[test]
+    fn test_visit_tmx() {
+        let mut counter = Counter::default();
+        let warnings = visit_tmx(INFINITE.as_bytes(), &mut counter).unwrap();
+
+        assert!(counter.info.infinite);
+        assert_eq!(counter.info.size, Vector2 { x: 4, y: 4 });
+        assert_eq!(counter.layers, vec!["Ground"]);
+        assert_eq!(counter.chunks, vec![([-1, 0].into(), 4), ([2, 2].into(), 4)]);
+        assert_eq!(counter.group_starts, vec![
+            ("Spawns".to_string(), 0),
+            ("Items".to_string(), 2),
+        ]);
+        assert_eq!(counter.groups, vec![("Spawns".to_string(), 2), ("Items".to_string(), 3)]);
+        assert_eq!(counter.properties, vec!["spawn"]);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].location.path, "map/objectgroup[name=Items]/object[id=3]");
+
+        let strict = LoadOptions { strict: true, ..LoadOptions::default() };
+        let error = visit_tmx_with(INFINITE.as_bytes(), &mut Counter::default(), &strict);
+        assert!(matches!(error, Err(TMXError::InvalidGid { gid: 9, .. })), "{:?}", error);
+
+        let mut files = HashMap::new();
+        files.insert(PathBuf::from("tilesets/items.tsx"), br#"
+        <tileset name="items" tilewidth="8" tileheight="8" tilecount="8" columns="2"/>
+        "#.to_vec());
+        let external = INFINITE.replace(
+            "<properties>",
+            r#"<tileset firstgid="5" source="tilesets/items.tsx"/><properties>"#,
+        );
+        let options = LoadOptions { resources: Some(&files), ..LoadOptions::default() };
+        let mut counter = Counter::default();
+        let warnings = visit_tmx_with(external.as_bytes(), &mut counter, &options).unwrap();
+
+        assert_eq!(counter.tilesets, vec!["items", "terrain"]);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+    }
+
+    #[test]
+    fn test_scan_map_header() {
+        let header = MapHeader::scan(INFINITE.replace("4,3,2,1", "A*A")).unwrap();
+
+        assert!(header.info.infinite);
+        assert_eq!(header.info.tile_size, Vector2 { x: 8, y: 8 });
+        assert_eq!(header.tilesets.len(), 1);
+        assert_eq!(header.tilesets[0].name, "terrain");
+        assert_eq!(header.properties["spawn"], PropertyValue::String("north".to_string()));
+
+        let external = r#"
+        <map orientation="isometric" width="2" height="2" tilewidth="8" tileheight="4">
+            <tileset firstgid="1" source="terrain.tsx"/>
+            <tileset firstgid="5" source="items.tsx"/>
+            <layer id="1" name="Ground" width="2" height="2">
+                <data encoding="base64" compression="zlib">not base64</data>
+            </layer>
+        </map>"#;
+
+        let (header, _) = MapHeader::scan_with(external.as_bytes(), &LoadOptions::default())
+            .unwrap();
+        let sources: Vec<_> = header.tilesets
+            .iter()
+            .map(|tileset| (tileset.firstgid, tileset.source.as_deref()))
+            .collect();
+
+        assert_eq!(header.info.orientation, Orientation::Isometric);
+        assert_eq!(sources, vec![(1, Some("terrain.tsx")), (5, Some("items.tsx"))]);
+        assert!(MapHeader::scan("<map><layer></map>").is_err());
+
+        let broken = INFINITE.replace("name=\"spawn\"", "name=\"spawn\" type=\"int\"");
+        let (header, warnings) = MapHeader::scan_with(broken.as_bytes(), &LoadOptions::default())
+            .unwrap();
+        assert!(header.properties.is_empty());
+        assert_eq!(warnings.len(), 1, "{:?}", warnings);
+        assert_eq!(warnings[0].location.path, "map/properties/property[name=spawn]");
+
+        let strict = LoadOptions { strict: true, ..LoadOptions::default() };
+        let error = MapHeader::scan_with(broken.as_bytes(), &strict).unwrap_err();
+        assert!(matches!(error, TMXError::Attribute { .. }), "{}", error);
+    }
+
+    #[test]
+    fn test_load_infinite_map() {
+        let (map, warnings) = Map::load_tmx_with(INFINITE.as_bytes(), &LoadOptions::default())
+            .unwrap();
+
+        assert_eq!(map.tiles, vec![
+            2, 0, 0, 0,
+            4, 0, 0, 0,
+            0, 0, 4, 3,
+            0, 0, 2, 1,
+        ]);
+        assert_eq!(map.object_groups.len(), 2);
+        assert_eq!(map.object_groups[0].objects.len(), 2);
+        assert_eq!(map.object_groups[1].name, "Items");
+        assert_eq!(map.object_groups[1].objects[0].id, 3);
+        assert_eq!(map.tilesets().len(), 1);
+
+        // Les tuiles du morceau situées à une abscisse négative sont signalées.
+        let dropped: Vec<&Warning> = warnings
+            .iter()
+            .filter(|warning| warning.field == "chunk")
+            .collect();
+        assert_eq!(dropped.len(), 1, "{:?}", warnings);
+        let message = &dropped[0].message;
+        assert!(message.starts_with("2 tiles of the chunk at (-1, 0)"), "{}", message);
+        assert_eq!(dropped[0].location.path, "map/layer[name=Ground]/data");
+
+        let strict = LoadOptions { strict: true, ..LoadOptions::default() };
+        let valid = INFINITE.replace("gid=\"9\"", "gid=\"2\"");
+        let inside = valid.replace("x=\"-1\"", "x=\"0\"");
+        assert!(Map::load_tmx_with(inside.as_bytes(), &strict).is_ok());
+        match Map::load_tmx_with(valid.as_bytes(), &strict) {
+            Err(TMXError::Attribute { name, .. }) => assert_eq!(name, "chunk"),
+            result => panic!("unexpected result: {:?}", result.map(|_| ())),
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::BinaryHeap;
+
+use mint::{Point2, Vector2};
+
+use crate::data::Map;
+use crate::grid::Neighbor;
+use crate::path::{CellCost, CornerCutting, OpenCell, move_weight};
+
+/// Grille de valeurs associées aux cellules d'une map, rangées dans l'ordre de
+/// ses tuiles.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    /// Nombre de colonnes et de lignes de la grille.
+    pub size: Vector2<u16>,
+    /// Valeurs des cellules, ligne par ligne.
+    pub cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Renvoie la valeur de la cellule passée en paramètre, ou `None` si celle-ci
+    /// se trouve hors de la grille.
+    pub fn get(&self, coords: Point2<u16>) -> Option<&T> {
+        if coords.x >= self.size.x || coords.y >= self.size.y {
+            return None;
+        }
+
+        self.cells.get(usize::from(coords.y) * usize::from(self.size.x) + usize::from(coords.x))
+    }
+}
+
+impl Map {
+    /// Calcule, pour chaque cellule de la map, le coût du plus court chemin
+    /// menant à la source la plus proche, à l'aide de l'algorithme de Dijkstra.
+    ///
+    /// Les déplacements et leur coût suivent le modèle de [`Map::find_path`] :
+    /// la valeur d'une cellule est le coût que renverrait une recherche de
+    /// chemin depuis celle-ci vers la source la plus proche. Les sources valent
+    /// 0, tandis que les cellules infranchissables et celles ne permettant
+    /// d'atteindre aucune source valent `None`. Les sources infranchissables ou
+    /// situées hors de la map sont ignorées.
+    pub fn distance_map<C: CellCost + ?Sized>(
+        &self,
+        sources: &[Point2<u16>],
+        cost: &C,
+        corner_cutting: CornerCutting,
+    ) -> Grid<Option<f32>> {
+        let distances = self.distances(sources, cost, corner_cutting);
+
+        let cells = distances.into_iter()
+            .map(|distance| Some(distance).filter(|distance| distance.is_finite()))
+            .collect();
+
+        Grid { size: self.size, cells }
+    }
+
+    /// Calcule, pour chaque cellule de la map, la voisine vers laquelle se
+    /// déplacer afin de rejoindre au plus vite la source la plus proche.
+    ///
+    /// Le champ suit les distances calculées par [`Map::distance_map`]. Les
+    /// sources, ainsi que les cellules ne permettant d'atteindre aucune source,
+    /// ne possèdent pas de voisine.
+    pub fn flow_field<C: CellCost + ?Sized>(
+        &self,
+        sources: &[Point2<u16>],
+        cost: &C,
+        corner_cutting: CornerCutting,
+    ) -> Grid<Option<Neighbor>> {
+        let distances = self.distances(sources, cost, corner_cutting);
+
+        let cells = (0..self.cell_count())
+            .map(|index| {
+                if distances[index] == 0.0 || distances[index].is_infinite() {
+                    return None;
+                }
+
+                self.steps(self.cell_coords(index), cost, corner_cutting)
+                    .into_iter()
+                    .map(|(neighbor, step)| {
+                        (neighbor, step + distances[self.cell_index(neighbor.coords)])
+                    })
+                    .fold(None, |best: Option<(Neighbor, f32)>, (neighbor, total)| match best {
+                        Some((_, best_total)) if best_total <= total => best,
+                        _ => Some((neighbor, total)),
+                    })
+                    .filter(|&(_, total)| total.is_finite())
+                    .map(|(neighbor, _)| neighbor)
+            })
+            .collect();
+
+        Grid { size: self.size, cells }
+    }
+
+    /// Calcule les distances vers la source la plus proche, infinies pour les
+    /// cellules ne permettant d'atteindre aucune source.
+    fn distances<C: CellCost + ?Sized>(
+        &self,
+        sources: &[Point2<u16>],
+        cost: &C,
+        corner_cutting: CornerCutting,
+    ) -> Vec<f32> {
+        let mut distances = vec![f32::INFINITY; self.cell_count()];
+        let mut open = BinaryHeap::new();
+
+        for &source in sources {
+            if self.contains_cell(source) && cost.cost(self, source).is_some() {
+                distances[self.cell_index(source)] = 0.0;
+                open.push(OpenCell { priority: 0.0, coords: source });
+            }
+        }
+
+        // Les distances sont propagées à rebours : se déplacer d'une voisine vers
+        // la cellule courante coûte le coût de cette dernière.
+        while let Some(OpenCell { priority, coords }) = open.pop() {
+            if priority > distances[self.cell_index(coords)] {
+                continue;
+            }
+
+            let entry = match cost.cost(self, coords) {
+                Some(entry) => entry,
+                None => continue,
+            };
+
+            for (neighbor, _) in self.steps(coords, cost, corner_cutting) {
+                let index = self.cell_index(neighbor.coords);
+                let distance = priority + entry * move_weight(neighbor);
+
+                if distance < distances[index] {
+                    distances[index] = distance;
+                    open.push(OpenCell { priority: distance, coords: neighbor.coords });
+                }
+            }
+        }
+
+        distances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use crate::data::{Orientation, Properties, PropertyValue, StaggerAxis, StaggerIndex, TileSet};
+    use crate::path::{PathOptions, TilePropertyCost};
+
+    /// Crée une map dont les cellules marquées `#` sont infranchissables et
+    /// celles marquées `~` coûtent 3.
+    fn terrain(orientation: Orientation, rows: &[&str]) -> Map {
+        let tiles = rows.concat().bytes()
+            .map(|cell| match cell {
+                b'#' => 2,
+                b'~' => 3,
+                _ => 1,
+            })
+            .collect();
+
+        let mut map = Map {
+            orientation,
+            size: Vector2 { x: rows[0].len() as u16, y: rows.len() as u16 },
+            tile_size: Vector2 { x: 16, y: 16 },
+            tiles,
+            ..Default::default()
+        };
+
+        let property = |value| Properties::from([(String::from("cost"), value)]);
+        map.add_tileset(TileSet {
+            firstgid: 1,
+            count: 3,
+            tiles_properties: BTreeMap::from([
+                (1, property(PropertyValue::Int(0))),
+                (2, property(PropertyValue::Float(3.0))),
+            ]),
+            ..Default::default()
+        });
+
+        map
+    }
+
+    #[test]
+    fn test_distance_map() {
+        let map = terrain(Orientation::Orthogonal, &[
+            "..~.",
+            "##~.",
+            ".#..",
+        ]);
+        let cost = TilePropertyCost::Cost(String::from("cost"));
+
+        let distances = map.distance_map(&[[0, 0].into()], &cost, CornerCutting::Never);
+        assert_eq!(distances.size, map.size);
+        assert_eq!(distances.cells, vec![
+            Some(0.0), Some(1.0), Some(2.0), Some(5.0),
+            None, None, Some(5.0), Some(6.0),
+            None, None, Some(8.0), Some(7.0),
+        ]);
+        assert_eq!(distances.get([2, 2].into()), Some(&Some(8.0)));
+        assert_eq!(distances.get([4, 0].into()), None);
+
+        let sources = [[0, 0].into(), [3, 2].into(), [9, 9].into(), [0, 1].into()];
+        let distances = map.distance_map(&sources, &cost, CornerCutting::Never);
+        assert_eq!(distances.cells[3..8], [Some(2.0), None, None, Some(2.0), Some(1.0)]);
+    }
+
+    #[test]
+    fn test_flow_field() {
+        let map = terrain(Orientation::Orthogonal, &[
+            "..~.",
+            "##~.",
+            ".#..",
+        ]);
+        let cost = TilePropertyCost::Cost(String::from("cost"));
+
+        let field = map.flow_field(&[[0, 0].into()], &cost, CornerCutting::Never);
+        let next = |x, y| field.get(Point2 { x, y }).unwrap().map(|neighbor| neighbor.coords);
+
+        assert_eq!(next(0, 0), None);
+        assert_eq!(next(0, 2), None);
+        assert_eq!(next(1, 0), Some([0, 0].into()));
+        assert_eq!(next(3, 2), Some([3, 1].into()));
+        assert_eq!(next(3, 0), Some([2, 0].into()));
+    }
+
+    #[test]
+    fn test_flow_field_orientations() {
+        let rows = ["......", ".#~#..", "..~...", "..#~#.", "......", ".#...."];
+        let cost = TilePropertyCost::Cost(String::from("cost"));
+        let sources = [Point2 { x: 0, y: 0 }, Point2 { x: 4, y: 4 }];
+
+        let orientations = [
+            Orientation::Orthogonal,
+            Orientation::Isometric,
+            Orientation::Staggered,
+            Orientation::Hexagonal,
+        ];
+
+        for &orientation in &orientations {
+            for &axis in &[StaggerAxis::XAxis, StaggerAxis::YAxis] {
+                for &index in &[StaggerIndex::Odd, StaggerIndex::Even] {
+                    let mut map = terrain(orientation, &rows);
+                    map.stagger_axis = axis;
+                    map.stagger_index = index;
+
+                    let corner_cutting = CornerCutting::BothOpen;
+                    let options = PathOptions { corner_cutting, ..Default::default() };
+                    let distances = map.distance_map(&sources, &cost, corner_cutting);
+                    let field = map.flow_field(&sources, &cost, corner_cutting);
+
+                    for (index, distance) in distances.cells.iter().enumerate() {
+                        let start = map.cell_coords(index);
+                        if cost.cost(&map, start).is_none() {
+                            assert_eq!(*distance, None);
+                            continue;
+                        }
+
+                        // La distance est celle du chemin vers la source la plus proche.
+                        let best = sources.iter()
+                            .filter_map(|&source| map.find_path(start, source, &cost, &options))
+                            .map(|path| path.cost)
+                            .fold(None, |best: Option<f32>, cost| {
+                                Some(best.map_or(cost, |best| best.min(cost)))
+                            });
+                        match (distance, best) {
+                            (Some(distance), Some(best)) => assert!((distance - best).abs() < 1e-4),
+                            (distance, best) => assert_eq!(distance.is_some(), best.is_some()),
+                        }
+
+                        // Suivre le champ mène à une source pour le coût annoncé.
+                        let mut coords = start;
+                        let mut total = 0.0;
+                        while let Some(neighbor) = field.get(coords).unwrap() {
+                            total += cost.cost(&map, neighbor.coords).unwrap()
+                                * move_weight(*neighbor);
+                            coords = neighbor.coords;
+                        }
+                        if let Some(distance) = distance {
+                            assert!(sources.contains(&coords), "{:?}", orientation);
+                            assert!((total - distance).abs() < 1e-4, "{:?}", orientation);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
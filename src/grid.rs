@@ -0,0 +1,276 @@
+use std::convert::TryFrom;
+
+use mint::Point2;
+
+use crate::data::{Map, Orientation, StaggerAxis};
+
+/// Direction d'une cellule voisine, telle qu'affichée à l'écran par Tiled : le
+/// nord désigne le haut de l'écran.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Direction {
+    /// Vers le haut.
+    North,
+    /// Vers le haut et la droite.
+    NorthEast,
+    /// Vers la droite.
+    East,
+    /// Vers le bas et la droite.
+    SouthEast,
+    /// Vers le bas.
+    South,
+    /// Vers le bas et la gauche.
+    SouthWest,
+    /// Vers la gauche.
+    West,
+    /// Vers le haut et la gauche.
+    NorthWest,
+}
+
+/// Cellule voisine renvoyée par [`Map::neighbors`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Neighbor {
+    /// Coordonnées de la cellule voisine.
+    pub coords: Point2<u16>,
+    /// Direction de la cellule voisine.
+    pub direction: Direction,
+    /// Indique si la cellule voisine partage un côté avec la cellule d'origine,
+    /// ou seulement un coin.
+    pub edge: bool,
+}
+
+impl Map {
+    /// Renvoie les cellules voisines de la cellule passée en paramètre, situées
+    /// dans les limites de la map.
+    ///
+    /// Une cellule d'une map orthogonale, isométrique ou décalée possède huit
+    /// voisines : quatre partageant un côté et quatre ne partageant qu'un coin,
+    /// que l'on peut écarter grâce à [`Neighbor::edge`]. Une cellule d'une map
+    /// hexagonale possède six voisines, partageant toutes un côté. Les voisines
+    /// d'une map décalée ou hexagonale dépendent de l'axe et de l'indice de
+    /// décalage, l'axe y étant utilisé par défaut.
+    ///
+    /// Les voisines sont renvoyées dans le sens des aiguilles d'une montre, en
+    /// partant du nord ou de la première direction suivante.
+    pub fn neighbors(&self, coords: Point2<u16>) -> Vec<Neighbor> {
+        use Direction::*;
+
+        let offsets: &[(Direction, i32, i32)] = match self.orientation {
+            Orientation::Orthogonal => &[
+                (North, 0, -1), (NorthEast, 1, -1), (East, 1, 0), (SouthEast, 1, 1),
+                (South, 0, 1), (SouthWest, -1, 1), (West, -1, 0), (NorthWest, -1, -1),
+            ],
+            Orientation::Isometric => &[
+                (North, -1, -1), (NorthEast, 0, -1), (East, 1, -1), (SouthEast, 1, 0),
+                (South, 1, 1), (SouthWest, 0, 1), (West, -1, 1), (NorthWest, -1, 0),
+            ],
+            Orientation::Staggered | Orientation::Hexagonal => {
+                match self.staggered_axis() {
+                    // Les colonnes décalées sont abaissées d'une demi-tuile.
+                    StaggerAxis::XAxis if self.is_staggered(coords.x) => &[
+                        (North, 0, -1), (NorthEast, 1, 0), (East, 2, 0), (SouthEast, 1, 1),
+                        (South, 0, 1), (SouthWest, -1, 1), (West, -2, 0), (NorthWest, -1, 0),
+                    ],
+                    StaggerAxis::XAxis => &[
+                        (North, 0, -1), (NorthEast, 1, -1), (East, 2, 0), (SouthEast, 1, 0),
+                        (South, 0, 1), (SouthWest, -1, 0), (West, -2, 0), (NorthWest, -1, -1),
+                    ],
+                    // Les lignes décalées sont déplacées d'une demi-tuile vers la droite.
+                    _ if self.is_staggered(coords.y) => &[
+                        (North, 0, -2), (NorthEast, 1, -1), (East, 1, 0), (SouthEast, 1, 1),
+                        (South, 0, 2), (SouthWest, 0, 1), (West, -1, 0), (NorthWest, 0, -1),
+                    ],
+                    _ => &[
+                        (North, 0, -2), (NorthEast, 0, -1), (East, 1, 0), (SouthEast, 0, 1),
+                        (South, 0, 2), (SouthWest, -1, 1), (West, -1, 0), (NorthWest, -1, -1),
+                    ],
+                }
+            }
+        };
+
+        offsets.iter()
+            .filter_map(|&(direction, dx, dy)| {
+                let edge = self.shares_edge(direction);
+
+                if self.orientation == Orientation::Hexagonal && !edge {
+                    return None;
+                }
+
+                let x = u16::try_from(i32::from(coords.x) + dx).ok()?;
+                let y = u16::try_from(i32::from(coords.y) + dy).ok()?;
+
+                if x >= self.size.x || y >= self.size.y {
+                    return None;
+                }
+
+                Some(Neighbor { coords: Point2 { x, y }, direction, edge })
+            })
+            .collect()
+    }
+
+    /// Indique si la cellule voisine située dans la direction passée en
+    /// paramètre partage un côté avec la cellule d'origine.
+    fn shares_edge(&self, direction: Direction) -> bool {
+        let diagonal = !matches!(
+            direction,
+            Direction::North | Direction::East | Direction::South | Direction::West
+        );
+
+        match (self.orientation, self.staggered_axis()) {
+            (Orientation::Orthogonal, _) => !diagonal,
+            // Les hexagones dont le côté plat est horizontal touchent leurs voisins
+            // du nord et du sud, et ceux dont le côté plat est vertical touchent
+            // leurs voisins de l'est et de l'ouest.
+            (Orientation::Hexagonal, StaggerAxis::XAxis) => {
+                diagonal || matches!(direction, Direction::North | Direction::South)
+            }
+            (Orientation::Hexagonal, _) => {
+                diagonal || matches!(direction, Direction::East | Direction::West)
+            }
+            _ => diagonal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mint::Vector2;
+
+    use crate::data::StaggerIndex;
+
+    fn neighbors(map: &Map, coords: [u16; 2]) -> Vec<(Direction, [u16; 2], bool)> {
+        map.neighbors(coords.into())
+            .into_iter()
+            .map(|neighbor| (neighbor.direction, neighbor.coords.into(), neighbor.edge))
+            .collect()
+    }
+
+    #[test]
+    fn test_neighbors() {
+        use Direction::*;
+
+        let mut map = Map { size: Vector2 { x: 4, y: 4 }, ..Default::default() };
+
+        assert_eq!(neighbors(&map, [0, 0]), vec![
+            (East, [1, 0], true),
+            (SouthEast, [1, 1], false),
+            (South, [0, 1], true),
+        ]);
+        assert_eq!(map.neighbors([2, 2].into()).len(), 8);
+        assert_eq!(neighbors(&map, [3, 3]).iter().filter(|(_, _, edge)| *edge).count(), 2);
+
+        map.orientation = Orientation::Isometric;
+        assert_eq!(neighbors(&map, [0, 0]), vec![
+            (SouthEast, [1, 0], true),
+            (South, [1, 1], false),
+            (SouthWest, [0, 1], true),
+        ]);
+
+        map.orientation = Orientation::Staggered;
+        assert_eq!(neighbors(&map, [1, 1]), vec![
+            (NorthEast, [2, 0], true),
+            (East, [2, 1], false),
+            (SouthEast, [2, 2], true),
+            (South, [1, 3], false),
+            (SouthWest, [1, 2], true),
+            (West, [0, 1], false),
+            (NorthWest, [1, 0], true),
+        ]);
+
+        map.stagger_index = StaggerIndex::Even;
+        assert_eq!(neighbors(&map, [1, 1]), vec![
+            (NorthEast, [1, 0], true),
+            (East, [2, 1], false),
+            (SouthEast, [1, 2], true),
+            (South, [1, 3], false),
+            (SouthWest, [0, 2], true),
+            (West, [0, 1], false),
+            (NorthWest, [0, 0], true),
+        ]);
+
+        map.orientation = Orientation::Hexagonal;
+        map.stagger_axis = StaggerAxis::XAxis;
+        assert_eq!(neighbors(&map, [2, 1]), vec![
+            (North, [2, 0], true),
+            (NorthEast, [3, 1], true),
+            (SouthEast, [3, 2], true),
+            (South, [2, 2], true),
+            (SouthWest, [1, 2], true),
+            (NorthWest, [1, 1], true),
+        ]);
+
+        map.stagger_index = StaggerIndex::Odd;
+        assert_eq!(neighbors(&map, [1, 1]), vec![
+            (North, [1, 0], true),
+            (NorthEast, [2, 1], true),
+            (SouthEast, [2, 2], true),
+            (South, [1, 2], true),
+            (SouthWest, [0, 2], true),
+            (NorthWest, [0, 1], true),
+        ]);
+
+        map.stagger_axis = StaggerAxis::YAxis;
+        assert_eq!(neighbors(&map, [1, 2]), vec![
+            (NorthEast, [1, 1], true),
+            (East, [2, 2], true),
+            (SouthEast, [1, 3], true),
+            (SouthWest, [0, 3], true),
+            (West, [0, 2], true),
+            (NorthWest, [0, 1], true),
+        ]);
+    }
+
+    /// Renvoie la direction d'un décalage dans le monde, l'axe des ordonnées
+    /// étant orienté vers le haut.
+    fn world_direction(dx: f32, dy: f32) -> Direction {
+        let sign = |value: f32| if value.abs() < 0.5 { 0 } else { value.signum() as i8 };
+
+        match (sign(dx), sign(dy)) {
+            (0, 1) => Direction::North,
+            (1, 1) => Direction::NorthEast,
+            (1, 0) => Direction::East,
+            (1, -1) => Direction::SouthEast,
+            (0, -1) => Direction::South,
+            (-1, -1) => Direction::SouthWest,
+            (-1, 0) => Direction::West,
+            _ => Direction::NorthWest,
+        }
+    }
+
+    #[test]
+    fn test_neighbor_directions() {
+        let mut map = Map {
+            size: Vector2 { x: 6, y: 6 },
+            tile_size: Vector2 { x: 16, y: 16 },
+            hex_side_length: 8,
+            ..Default::default()
+        };
+
+        let orientations = [
+            Orientation::Orthogonal,
+            Orientation::Isometric,
+            Orientation::Staggered,
+            Orientation::Hexagonal,
+        ];
+
+        for &orientation in &orientations {
+            for &axis in &[StaggerAxis::XAxis, StaggerAxis::YAxis] {
+                for &index in &[StaggerIndex::Odd, StaggerIndex::Even] {
+                    map.orientation = orientation;
+                    map.stagger_axis = axis;
+                    map.stagger_index = index;
+
+                    for &coords in &[[2, 3], [3, 2]] {
+                        let center = map.to_world_coords(coords.into());
+
+                        for neighbor in map.neighbors(coords.into()) {
+                            let other = map.to_world_coords(neighbor.coords);
+                            let direction = world_direction(other.x - center.x, other.y - center.y);
+                            assert_eq!(neighbor.direction, direction, "{:?}", map);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,293 @@
+use std::convert::TryFrom;
+use std::ops::{Add, Mul, Sub};
+
+use mint::Point2;
+
+use crate::data::{Map, StaggerAxis, StaggerIndex};
+
+/// Coordonnées axiales d'un hexagone.
+///
+/// L'axe `q` suit les colonnes et l'axe `r` les lignes de la map, de sorte que
+/// l'origine coïncide avec la tuile (0, 0).
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Axial {
+    /// Coordonnée sur l'axe `q`.
+    pub q: i32,
+    /// Coordonnée sur l'axe `r`.
+    pub r: i32,
+}
+
+impl Axial {
+    /// Crée de nouvelles coordonnées axiales.
+    pub fn new(q: i32, r: i32) -> Self {
+        Self { q, r }
+    }
+}
+
+impl From<Cube> for Axial {
+    fn from(cube: Cube) -> Self {
+        Self { q: cube.q, r: cube.r }
+    }
+}
+
+/// Coordonnées cubiques d'un hexagone, dont la somme des composantes est nulle.
+///
+/// Ces coordonnées permettent de calculer des distances, des rotations, des
+/// anneaux et des lignes d'hexagones.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Cube {
+    /// Coordonnée sur l'axe `q`.
+    pub q: i32,
+    /// Coordonnée sur l'axe `r`.
+    pub r: i32,
+    /// Coordonnée sur l'axe `s`, égale à `-q - r`.
+    pub s: i32,
+}
+
+impl Cube {
+    /// Décalages des six hexagones voisins, dans le sens des aiguilles d'une
+    /// montre tel qu'affiché par Tiled, en partant de l'axe `q` croissant.
+    pub const DIRECTIONS: [Cube; 6] = [
+        Cube { q: 1, r: 0, s: -1 },
+        Cube { q: 0, r: 1, s: -1 },
+        Cube { q: -1, r: 1, s: 0 },
+        Cube { q: -1, r: 0, s: 1 },
+        Cube { q: 0, r: -1, s: 1 },
+        Cube { q: 1, r: -1, s: 0 },
+    ];
+
+    /// Crée de nouvelles coordonnées cubiques à partir des axes `q` et `r`.
+    pub fn new(q: i32, r: i32) -> Self {
+        Self { q, r, s: -q - r }
+    }
+
+    /// Renvoie le nombre de pas séparant deux hexagones.
+    pub fn distance(self, other: Self) -> u32 {
+        let offset = self - other;
+        (offset.q.unsigned_abs() + offset.r.unsigned_abs() + offset.s.unsigned_abs()) / 2
+    }
+
+    /// Renvoie l'hexagone obtenu par une rotation de 60° dans le sens des
+    /// aiguilles d'une montre autour de l'origine.
+    pub fn rotate_right(self) -> Self {
+        Self { q: -self.r, r: -self.s, s: -self.q }
+    }
+
+    /// Renvoie l'hexagone obtenu par une rotation de 60° dans le sens inverse
+    /// des aiguilles d'une montre autour de l'origine.
+    pub fn rotate_left(self) -> Self {
+        Self { q: -self.s, r: -self.q, s: -self.r }
+    }
+
+    /// Renvoie l'anneau des hexagones situés à la distance passée en paramètre,
+    /// dans le sens des aiguilles d'une montre.
+    ///
+    /// Un anneau de rayon nul ne contient que l'hexagone lui-même.
+    pub fn ring(self, radius: u16) -> Vec<Self> {
+        if radius == 0 {
+            return vec![self];
+        }
+
+        let radius = i32::from(radius);
+        let mut ring = Vec::with_capacity(6 * radius as usize);
+        let mut current = self + Self::DIRECTIONS[4] * radius;
+
+        for &direction in &Self::DIRECTIONS {
+            for _ in 0..radius {
+                ring.push(current);
+                current = current + direction;
+            }
+        }
+
+        ring
+    }
+
+    /// Renvoie les hexagones traversés par le segment reliant les centres de deux
+    /// hexagones, extrémités comprises.
+    pub fn line(self, other: Self) -> Vec<Self> {
+        let steps = self.distance(other);
+
+        // Le léger décalage évite que le segment ne longe exactement la frontière
+        // entre deux hexagones, ce qui rendrait l'arrondi ambigu.
+        let start = [self.q as f32 + 1e-6, self.r as f32 + 1e-6];
+        let end = [other.q as f32 + 1e-6, other.r as f32 + 1e-6];
+
+        (0..=steps)
+            .map(|step| {
+                let t = if steps == 0 { 0.0 } else { step as f32 / steps as f32 };
+                Self::round(start[0] + (end[0] - start[0]) * t, start[1] + (end[1] - start[1]) * t)
+            })
+            .collect()
+    }
+
+    /// Arrondit des coordonnées fractionnaires à l'hexagone les contenant.
+    fn round(q: f32, r: f32) -> Self {
+        let s = -q - r;
+        let (mut rq, mut rr, rs) = (q.round(), r.round(), s.round());
+        let (dq, dr, ds) = ((rq - q).abs(), (rr - r).abs(), (rs - s).abs());
+
+        if dq > dr && dq > ds {
+            rq = -rr - rs;
+        } else if dr > ds {
+            rr = -rq - rs;
+        }
+
+        Self::new(rq as i32, rr as i32)
+    }
+}
+
+impl From<Axial> for Cube {
+    fn from(axial: Axial) -> Self {
+        Self::new(axial.q, axial.r)
+    }
+}
+
+impl Add for Cube {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self { q: self.q + other.q, r: self.r + other.r, s: self.s + other.s }
+    }
+}
+
+impl Sub for Cube {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self { q: self.q - other.q, r: self.r - other.r, s: self.s - other.s }
+    }
+}
+
+impl Mul<i32> for Cube {
+    type Output = Self;
+
+    fn mul(self, factor: i32) -> Self {
+        Self { q: self.q * factor, r: self.r * factor, s: self.s * factor }
+    }
+}
+
+impl Map {
+    /// Convertit les coordonnées d'une tuile de la map en coordonnées cubiques.
+    ///
+    /// La conversion respecte l'axe et l'indice de décalage de la map, l'axe y
+    /// étant utilisé par défaut. Elle n'a de sens que sur une map hexagonale.
+    pub fn to_cube_coords(&self, map_coords: Point2<u16>) -> Cube {
+        let x = i32::from(map_coords.x);
+        let y = i32::from(map_coords.y);
+
+        match self.staggered_axis() {
+            StaggerAxis::XAxis => Cube::new(x, y - self.stagger_shift(x)),
+            _ => Cube::new(x - self.stagger_shift(y), y),
+        }
+    }
+
+    /// Convertit des coordonnées cubiques en coordonnées d'une tuile de la map,
+    /// ou renvoie `None` si celle-ci se trouve hors de la map.
+    ///
+    /// Il s'agit de la conversion inverse de [`Map::to_cube_coords`].
+    pub fn from_cube_coords(&self, cube: Cube) -> Option<Point2<u16>> {
+        let (x, y) = match self.staggered_axis() {
+            StaggerAxis::XAxis => (cube.q, cube.r + self.stagger_shift(cube.q)),
+            _ => (cube.q + self.stagger_shift(cube.r), cube.r),
+        };
+
+        let x = u16::try_from(x).ok().filter(|&x| x < self.size.x)?;
+        let y = u16::try_from(y).ok().filter(|&y| y < self.size.y)?;
+
+        Some(Point2 { x, y })
+    }
+
+    /// Renvoie le décalage, en nombre de tuiles, introduit par les lignes ou
+    /// colonnes décalées précédant celle passée en paramètre.
+    fn stagger_shift(&self, index: i32) -> i32 {
+        let parity = index & 1;
+
+        match self.stagger_index {
+            StaggerIndex::Odd => (index - parity) / 2,
+            StaggerIndex::Even => (index + parity) / 2,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mint::Vector2;
+
+    use crate::data::Orientation;
+
+    fn hex_map(axis: StaggerAxis, index: StaggerIndex) -> Map {
+        Map {
+            orientation: Orientation::Hexagonal,
+            size: Vector2 { x: 6, y: 5 },
+            tile_size: Vector2 { x: 16, y: 16 },
+            hex_side_length: 8,
+            stagger_axis: axis,
+            stagger_index: index,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cube_coords() {
+        let map = hex_map(StaggerAxis::YAxis, StaggerIndex::Odd);
+        assert_eq!(map.to_cube_coords([1, 1].into()), Cube::new(1, 1));
+        assert_eq!(map.to_cube_coords([0, 2].into()), Cube::new(-1, 2));
+        assert_eq!(map.from_cube_coords(Cube::new(-1, 2)), Some([0, 2].into()));
+        assert_eq!(map.from_cube_coords(Cube::new(-1, 1)), None);
+        assert_eq!(map.from_cube_coords(Cube::new(0, 5)), None);
+
+        let map = hex_map(StaggerAxis::XAxis, StaggerIndex::Even);
+        assert_eq!(map.to_cube_coords([1, 1].into()), Cube::new(1, 0));
+        assert_eq!(map.to_cube_coords([2, 0].into()), Cube::new(2, -1));
+        assert_eq!(Axial::from(map.to_cube_coords([2, 0].into())), Axial::new(2, -1));
+
+        for &axis in &[StaggerAxis::XAxis, StaggerAxis::YAxis] {
+            for &index in &[StaggerIndex::Odd, StaggerIndex::Even] {
+                let map = hex_map(axis, index);
+
+                for x in 0..map.size.x {
+                    for y in 0..map.size.y {
+                        let coords = Point2 { x, y };
+                        let cube = map.to_cube_coords(coords);
+                        assert_eq!(cube.q + cube.r + cube.s, 0);
+                        assert_eq!(map.from_cube_coords(cube), Some(coords));
+
+                        // Les voisines de la map sont à un pas en coordonnées cubiques.
+                        for neighbor in map.neighbors(coords) {
+                            assert_eq!(cube.distance(map.to_cube_coords(neighbor.coords)), 1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_cube_operations() {
+        let origin = Cube::default();
+        let cube = Cube::new(2, -1);
+
+        assert_eq!(origin.distance(cube), 2);
+        assert_eq!(Cube::new(-3, 1).distance(cube), 5);
+
+        assert_eq!(Cube::DIRECTIONS[0].rotate_right(), Cube::DIRECTIONS[1]);
+        assert_eq!(Cube::DIRECTIONS[0].rotate_left(), Cube::DIRECTIONS[5]);
+        assert_eq!(cube.rotate_right().rotate_left(), cube);
+        assert_eq!((0..6).fold(cube, |cube, _| cube.rotate_right()), cube);
+
+        assert_eq!(cube.ring(0), vec![cube]);
+        assert_eq!(origin.ring(1).len(), 6);
+
+        let ring = cube.ring(3);
+        assert_eq!(ring.len(), 18);
+        assert!(ring.iter().all(|&other| cube.distance(other) == 3));
+
+        let line = origin.line(Cube::new(3, -1));
+        assert_eq!(line.first(), Some(&origin));
+        assert_eq!(line.last(), Some(&Cube::new(3, -1)));
+        assert_eq!(line.len(), 4);
+        assert!(line.windows(2).all(|pair| pair[0].distance(pair[1]) == 1));
+        assert_eq!(cube.line(cube), vec![cube]);
+    }
+}
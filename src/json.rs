@@ -0,0 +1,1332 @@
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+use mint::Vector2;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde::de::Error as _;
+use serde_json::Value;
+
+use crate::data::{self, Color, Encoding, EnumValue, Image, Map, Object, ObjectGroup, Orientation,
+                  Properties, PropertyValue, StaggerAxis, StaggerIndex, Tile, TileSet,
+                  TilesOrigin};
+use crate::deserial::{decode_tiles_data, outside_tiles};
+use crate::diagnostic::{self, Resources};
+use crate::events::{Chunk, LayerInfo, MapBuilder, MapHandler, MapInfo, ObjectGroupInfo};
+use crate::serial::encode_base64_data;
+
+pub use serde_json::Error as TMJError;
+
+/// Version du format JSON de Tiled utilisée lors de l'écriture.
+const VERSION: &str = "1.10";
+
+/// Indique si la valeur passée en paramètre est nulle, afin de ne pas l'écrire.
+#[inline]
+fn is_zero(value: &u16) -> bool {
+    *value == 0
+}
+
+/// Écrit la map au format `.tmj`, en encodant son calque de tuiles avec
+/// l'encodage passé en paramètre.
+pub(crate) fn write_map<W: Write>(
+    map: &Map,
+    writer: W,
+    encoding: Encoding,
+) -> Result<(), TMJError> {
+    serde_json::to_writer_pretty(writer, &JsonMap::from_map(map, encoding)?)
+}
+
+/// Écrit le contenu du jeu de tuiles au format `.tsj`.
+pub(crate) fn write_tileset<W: Write>(tileset: &TileSet, writer: W) -> Result<(), TMJError> {
+    let mut json = JsonTileSet::content(tileset);
+    json.kind = "tileset";
+    json.version = VERSION;
+    json.firstgid = None;
+
+    serde_json::to_writer_pretty(writer, &json)
+}
+
+/// Convertit une coordonnée exprimée par un nombre flottant en pixels entiers.
+///
+/// Une coordonnée négative ou trop grande pour être représentée est invalide.
+fn deserialize_pixels<'de, D>(deserializer: D) -> Result<u16, D::Error>
+where D: Deserializer<'de>
+{
+    let value = f64::deserialize(deserializer)?;
+    let pixels = value.round();
+
+    if (0.0..=f64::from(u16::MAX)).contains(&pixels) {
+        Ok(pixels as u16)
+    } else {
+        Err(D::Error::custom(format!("{} is not a valid pixel coordinate", value)))
+    }
+}
+
+/// Représentation JSON d'une map.
+///
+/// Les champs ignorés lors du chargement ne sont présents que pour produire un
+/// fichier pouvant être ouvert par Tiled.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct JsonMap {
+    #[serde(rename = "type", skip_deserializing)]
+    kind: &'static str,
+    #[serde(skip_deserializing)]
+    version: &'static str,
+    orientation: Orientation,
+    #[serde(skip_deserializing)]
+    renderorder: &'static str,
+    width: u16,
+    height: u16,
+    tilewidth: u16,
+    tileheight: u16,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    hexsidelength: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    staggeraxis: Option<StaggerAxis>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    staggerindex: Option<StaggerIndex>,
+    #[serde(skip_deserializing)]
+    nextlayerid: u16,
+    #[serde(skip_deserializing)]
+    nextobjectid: u16,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<JsonProperty>,
+    #[serde(default)]
+    tilesets: Vec<JsonTileSet>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+impl JsonMap {
+    /// Construit la représentation JSON de la map, en encodant son calque de
+    /// tuiles avec l'encodage passé en paramètre.
+    fn from_map(map: &Map, encoding: Encoding) -> Result<Self, TMJError> {
+        let mut layers = vec![JsonLayer::TileLayer(JsonTileLayer::from_map(map, encoding)?)];
+        layers.extend(map.object_groups.iter().map(|group| JsonLayer::ObjectGroup(group.into())));
+
+        let (next_layer_id, next_object_id) = map.next_ids();
+
+        Ok(JsonMap {
+            kind: "map",
+            version: VERSION,
+            orientation: map.orientation,
+            renderorder: "right-down",
+            width: map.size.x,
+            height: map.size.y,
+            tilewidth: map.tile_size.x,
+            tileheight: map.tile_size.y,
+            infinite: false,
+            hexsidelength: map.hex_side_length,
+            staggeraxis: Some(map.stagger_axis).filter(|&axis| axis != StaggerAxis::None),
+            staggerindex: Some(map.stagger_index).filter(|_| map.stagger_axis != StaggerAxis::None),
+            nextlayerid: next_layer_id,
+            nextobjectid: next_object_id,
+            properties: from_properties(&map.properties),
+            tilesets: map.tilesets()
+                .iter()
+                .map(|tileset| match tileset.source {
+                    Some(_) => JsonTileSet::reference(tileset),
+                    None => JsonTileSet::content(tileset),
+                })
+                .collect(),
+            layers,
+        })
+    }
+
+    /// Convertit la représentation JSON de la map en [`Map`].
+    ///
+    /// Les calques sont transmis au même constructeur que lors de la lecture d'un
+    /// fichier `.tmx`, en parcourant les groupes de calques. Les jeux de tuiles
+    /// externes sont chargés depuis les fichiers accessibles depuis la map
+    /// lorsque ceux-ci sont connus ; sinon, seule leur référence est conservée.
+    pub(crate) fn into_map(self, resources: Option<Resources<'_>>) -> Result<Map, TMJError> {
+        let info = MapInfo {
+            size: [self.width, self.height].into(),
+            tile_size: [self.tilewidth, self.tileheight].into(),
+            orientation: self.orientation,
+            stagger_axis: self.staggeraxis.unwrap_or(StaggerAxis::None),
+            stagger_index: self.staggerindex.unwrap_or_default(),
+            hex_side_length: self.hexsidelength,
+            infinite: self.infinite,
+        };
+
+        let mut builder = MapBuilder::default();
+        builder.header(&info);
+        send_layers(&mut builder, &info, self.layers)?;
+
+        let mut map = builder.build(info);
+        map.properties = into_properties(self.properties)?;
+
+        let tilesets = self.tilesets
+            .into_iter()
+            .map(|json| match (&json.source, resources) {
+                (Some(_), Some(resources)) => json.load_external(resources),
+                _ => json.into_tileset(resources),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        map.add_tilesets(tilesets);
+
+        Ok(map)
+    }
+}
+
+/// Représentation JSON d'un jeu de tuiles.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub(crate) struct JsonTileSet {
+    #[serde(rename = "type", skip_deserializing, skip_serializing_if = "str::is_empty")]
+    kind: &'static str,
+    #[serde(skip_deserializing, skip_serializing_if = "str::is_empty")]
+    version: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    firstgid: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    tilewidth: u16,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    tileheight: u16,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    tilecount: u16,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    columns: u16,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    spacing: u16,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    margin: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    imagewidth: u16,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    imageheight: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transparentcolor: Option<Color>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tiles: Vec<JsonTile>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<JsonProperty>,
+}
+
+impl JsonTileSet {
+    /// Construit la référence vers un jeu de tuiles externe à la map.
+    fn reference(tileset: &TileSet) -> Self {
+        JsonTileSet {
+            firstgid: Some(tileset.firstgid),
+            source: tileset.source.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Charge le contenu du jeu de tuiles externe référencé, en conservant le
+    /// `firstgid` que lui attribue la map.
+    ///
+    /// Le fichier est lu au format `.tsx` lorsque son contenu est du XML, et au
+    /// format `.tsj` sinon.
+    fn load_external(self, resources: Resources<'_>) -> Result<TileSet, TMJError> {
+        let source = self.source.unwrap_or_default();
+        let path = resources.resolve(&source);
+        let mut data = Vec::new();
+
+        resources.reader
+            .open(&path)
+            .and_then(|mut file| file.read_to_end(&mut data))
+            .map_err(|e| TMJError::custom(format!("cannot read \"{}\" - {}", source, e)))?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let resources = Resources { dir, reader: resources.reader };
+
+        let external = if data.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'<') {
+            let seed = PhantomData::<TileSet>;
+
+            diagnostic::load_tmx(data.as_slice(), true, Some(resources), None, seed).0
+                .map_err(|e| TMJError::custom(format!("cannot load \"{}\" - {}", source, e)))?
+        } else {
+            serde_json::from_slice::<JsonTileSet>(&data)?.into_tileset(Some(resources))?
+        };
+
+        Ok(TileSet {
+            firstgid: self.firstgid.unwrap_or(TileSet::default().firstgid),
+            source: Some(source),
+            path: Some(path),
+            ..external
+        })
+    }
+
+    /// Construit la représentation JSON du contenu d'un jeu de tuiles.
+    ///
+    /// Le format JSON ne permettant pas d'intégrer le contenu d'une image au
+    /// fichier, seule la source des images est conservée.
+    fn content(tileset: &TileSet) -> Self {
+        let mut json = JsonTileSet {
+            firstgid: Some(tileset.firstgid),
+            name: Some(tileset.name.clone()),
+            tilewidth: tileset.size.x,
+            tileheight: tileset.size.y,
+            tilecount: tileset.count,
+            columns: tileset.columns,
+            spacing: tileset.spacing,
+            margin: tileset.margin,
+            properties: from_properties(&tileset.properties),
+            ..Default::default()
+        };
+
+        let mut ids: Vec<u16> = tileset.tiles_properties.keys().copied().collect();
+
+        match &tileset.origin {
+            Some(TilesOrigin::Image(image)) => {
+                json.image = Some(image.source.clone());
+                json.imagewidth = image.size.x;
+                json.imageheight = image.size.y;
+                json.transparentcolor = image.trans;
+            }
+            Some(TilesOrigin::Collection(tiles)) => ids.extend(tiles.keys()),
+            None => (),
+        }
+
+        ids.sort_unstable();
+        ids.dedup();
+
+        json.tiles = ids
+            .into_iter()
+            .map(|id| {
+                let image = match &tileset.origin {
+                    Some(TilesOrigin::Collection(tiles)) => tiles.get(&id).map(|tile| &tile.image),
+                    _ => None,
+                };
+
+                JsonTile {
+                    id,
+                    image: image.map(|image| image.source.clone()),
+                    imagewidth: image.map_or(0, |image| image.size.x),
+                    imageheight: image.map_or(0, |image| image.size.y),
+                    properties: tileset.tile_properties(id)
+                        .map(from_properties)
+                        .unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        json
+    }
+}
+
+impl TryFrom<JsonTileSet> for TileSet {
+    type Error = TMJError;
+
+    #[inline]
+    fn try_from(json: JsonTileSet) -> Result<Self, Self::Error> {
+        json.into_tileset(None)
+    }
+}
+
+impl JsonTileSet {
+    /// Convertit la représentation JSON du jeu de tuiles en [`TileSet`].
+    ///
+    /// Le chemin d'accès de chaque image est résolu par rapport au fichier
+    /// contenant le jeu lorsque celui-ci est connu.
+    fn into_tileset(self, resources: Option<Resources<'_>>) -> Result<TileSet, TMJError> {
+        let default = TileSet::default();
+        let image = |source: String, size: Vector2<u16>| Image {
+            path: resources.map(|resources| resources.resolve(&source)),
+            ..Image::new(source, size)
+        };
+
+        let mut collection = Vec::new();
+        let mut tiles_properties = BTreeMap::new();
+
+        for tile in self.tiles {
+            if !tile.properties.is_empty() {
+                tiles_properties.insert(tile.id, into_properties(tile.properties)?);
+            }
+
+            if let Some(source) = tile.image {
+                let size = [tile.imagewidth, tile.imageheight].into();
+                collection.push(Tile::new(tile.id, image(source, size)));
+            }
+        }
+
+        let origin = match self.image {
+            Some(source) => {
+                let mut image = image(source, [self.imagewidth, self.imageheight].into());
+                image.trans = self.transparentcolor;
+
+                Some(TilesOrigin::Image(image))
+            }
+            None if collection.is_empty() => None,
+            None => Some(TilesOrigin::new_collection_from(collection)),
+        };
+
+        Ok(TileSet {
+            firstgid: self.firstgid.unwrap_or(default.firstgid),
+            source: self.source,
+            path: None,
+            size: [self.tilewidth, self.tileheight].into(),
+            count: self.tilecount,
+            columns: self.columns,
+            spacing: self.spacing,
+            margin: self.margin,
+            name: self.name.unwrap_or(default.name),
+            origin,
+            properties: into_properties(self.properties)?,
+            tiles_properties,
+        })
+    }
+}
+
+/// Représentation JSON d'une tuile d'un jeu de tuiles.
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonTile {
+    id: u16,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    imagewidth: u16,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    imageheight: u16,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<JsonProperty>,
+}
+
+/// Transmet les calques passés en paramètre au constructeur de la map, en
+/// parcourant les groupes de calques.
+///
+/// Le format JSON n'étant pas lu valeur par valeur, les tuiles d'une map infinie
+/// situées hors de la map ne peuvent être ignorées : elles font échouer le
+/// chargement.
+fn send_layers(
+    builder: &mut MapBuilder,
+    info: &MapInfo,
+    layers: Vec<JsonLayer>,
+) -> Result<(), TMJError> {
+    for layer in layers {
+        match layer {
+            JsonLayer::TileLayer(layer) => {
+                builder.layer_start(&LayerInfo {
+                    id: layer.id,
+                    name: layer.name.clone(),
+                    size: [layer.width, layer.height].into(),
+                });
+
+                for chunk in layer.into_chunks()? {
+                    let outside = Some(info.size)
+                        .filter(|_| info.infinite)
+                        .map_or(0, |size| outside_tiles(&chunk, size));
+
+                    if outside > 0 {
+                        return Err(TMJError::custom(format!(
+                            "{} tiles of the chunk at ({}, {}) lie outside the map",
+                            outside, chunk.coords.x, chunk.coords.y,
+                        )));
+                    }
+
+                    builder.layer_chunk(chunk);
+                }
+            }
+            JsonLayer::ObjectGroup(group) => {
+                let ObjectGroup { id, name, objects, properties } = group.try_into()?;
+                builder.object_group_start(&ObjectGroupInfo { id, name: name.clone() });
+
+                for object in objects {
+                    builder.object(object);
+                }
+
+                builder.object_group(ObjectGroup { id, name, objects: Vec::new(), properties });
+            }
+            JsonLayer::Group(group) => send_layers(builder, info, group.layers)?,
+            JsonLayer::Other => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Représentation JSON d'un calque.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(tag = "type")]
+enum JsonLayer {
+    #[serde(rename = "tilelayer")]
+    TileLayer(JsonTileLayer),
+    #[serde(rename = "objectgroup")]
+    ObjectGroup(JsonObjectGroup),
+    #[serde(rename = "group")]
+    Group(JsonGroupLayer),
+    #[serde(other)]
+    Other,
+}
+
+/// Représentation JSON d'un groupe de calques.
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonGroupLayer {
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+/// Représentation JSON d'un calque de tuiles.
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonTileLayer {
+    #[serde(default)]
+    id: u16,
+    #[serde(default)]
+    name: String,
+    #[serde(skip_deserializing)]
+    x: i32,
+    #[serde(skip_deserializing)]
+    y: i32,
+    #[serde(default)]
+    width: u16,
+    #[serde(default)]
+    height: u16,
+    #[serde(skip_deserializing)]
+    opacity: f32,
+    #[serde(skip_deserializing)]
+    visible: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encoding: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compression: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    data: Option<JsonTilesData>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    chunks: Vec<JsonChunk>,
+}
+
+/// Représentation JSON d'un morceau du contenu d'un calque de tuiles d'une map
+/// infinie.
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonChunk {
+    x: i32,
+    y: i32,
+    width: u16,
+    height: u16,
+    data: JsonTilesData,
+}
+
+impl JsonTileLayer {
+    /// Construit la représentation JSON du calque de tuiles de la map.
+    fn from_map(map: &Map, encoding: Encoding) -> Result<Self, TMJError> {
+        let (data, encoding, compression) = match encoding {
+            Encoding::Csv => {
+                let tiles = map.tiles.iter().copied().map(u32::from).collect();
+                (JsonTilesData::Array(tiles), None, None)
+            }
+            Encoding::Base64(compression) => {
+                let data = encode_base64_data(&map.tiles, compression).map_err(TMJError::io)?;
+
+                (
+                    JsonTilesData::Encoded(data),
+                    Some(String::from("base64")),
+                    compression.map(|compression| compression.to_string()),
+                )
+            }
+        };
+
+        Ok(JsonTileLayer {
+            id: map.tile_layer_id(),
+            name: String::from("Tile Layer 1"),
+            x: 0,
+            y: 0,
+            width: map.size.x,
+            height: map.size.y,
+            opacity: 1.0,
+            visible: true,
+            encoding,
+            compression,
+            data: Some(data),
+            chunks: Vec::new(),
+        })
+    }
+
+    /// Décode le contenu du calque en morceaux, un seul morceau couvrant tout le
+    /// calque lorsque celui-ci n'est pas découpé.
+    fn into_chunks(self) -> Result<Vec<Chunk>, TMJError> {
+        let encoding = self.encoding.as_deref();
+        let compression = self.compression.as_deref();
+
+        if self.chunks.is_empty() {
+            return Ok(vec![Chunk {
+                coords: [0, 0].into(),
+                size: [self.width, self.height].into(),
+                tiles: decode_tiles(self.data, encoding, compression)?,
+            }]);
+        }
+
+        self.chunks
+            .into_iter()
+            .map(|chunk| Ok(Chunk {
+                coords: [chunk.x, chunk.y].into(),
+                size: [chunk.width, chunk.height].into(),
+                tiles: decode_tiles(Some(chunk.data), encoding, compression)?,
+            }))
+            .collect()
+    }
+}
+
+/// Décode la liste des identifiants globaux d'un contenu de calque de tuiles,
+/// sans leurs bits de symétrie et de rotation.
+fn decode_tiles(
+    data: Option<JsonTilesData>,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u16>, TMJError> {
+    match data {
+        Some(JsonTilesData::Array(tiles)) => tiles
+            .into_iter()
+            .map(|gid| {
+                data::tile_gid(gid)
+                    .ok_or_else(|| TMJError::custom(format!("{} is not a valid gid", gid)))
+            })
+            .collect(),
+        Some(JsonTilesData::Encoded(data)) => {
+            decode_tiles_data(&data, encoding, compression).map_err(TMJError::custom)
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Contenu d'un calque de tuiles, sous la forme d'un tableau ou d'une chaîne de
+/// caractères encodée.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+enum JsonTilesData {
+    Array(Vec<u32>),
+    Encoded(String),
+}
+
+/// Représentation JSON d'un groupe d'objets.
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonObjectGroup {
+    #[serde(default)]
+    id: u16,
+    #[serde(default)]
+    name: String,
+    #[serde(skip_deserializing)]
+    x: i32,
+    #[serde(skip_deserializing)]
+    y: i32,
+    #[serde(skip_deserializing)]
+    opacity: f32,
+    #[serde(skip_deserializing)]
+    visible: bool,
+    #[serde(skip_deserializing)]
+    draworder: &'static str,
+    #[serde(default)]
+    objects: Vec<JsonObject>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<JsonProperty>,
+}
+
+impl From<&ObjectGroup> for JsonObjectGroup {
+    fn from(group: &ObjectGroup) -> Self {
+        JsonObjectGroup {
+            id: group.id,
+            name: group.name.clone(),
+            x: 0,
+            y: 0,
+            opacity: 1.0,
+            visible: true,
+            draworder: "topdown",
+            objects: group.objects.iter().map(JsonObject::from).collect(),
+            properties: from_properties(&group.properties),
+        }
+    }
+}
+
+impl TryFrom<JsonObjectGroup> for ObjectGroup {
+    type Error = TMJError;
+
+    fn try_from(json: JsonObjectGroup) -> Result<Self, Self::Error> {
+        Ok(ObjectGroup {
+            id: json.id,
+            name: json.name,
+            objects: json.objects
+                .into_iter()
+                .map(Object::try_from)
+                .collect::<Result<_, _>>()?,
+            properties: into_properties(json.properties)?,
+        })
+    }
+}
+
+/// Représentation JSON d'un objet.
+#[derive(Debug, Deserialize, Serialize)]
+struct JsonObject {
+    #[serde(default)]
+    id: u16,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    gid: u16,
+    #[serde(skip_deserializing)]
+    name: String,
+    #[serde(default, deserialize_with = "deserialize_pixels")]
+    x: u16,
+    #[serde(default, deserialize_with = "deserialize_pixels")]
+    y: u16,
+    #[serde(default, deserialize_with = "deserialize_pixels")]
+    width: u16,
+    #[serde(default, deserialize_with = "deserialize_pixels")]
+    height: u16,
+    #[serde(skip_deserializing)]
+    rotation: f32,
+    #[serde(skip_deserializing)]
+    visible: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<JsonProperty>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    template: Option<String>,
+}
+
+impl From<&Object> for JsonObject {
+    fn from(object: &Object) -> Self {
+        JsonObject {
+            id: object.id,
+            gid: object.valid_gid().unwrap_or(0),
+            name: String::new(),
+            x: object.coords.x,
+            y: object.coords.y,
+            width: object.size.x,
+            height: object.size.y,
+            rotation: 0.0,
+            visible: true,
+            properties: from_properties(&object.properties),
+            template: object.template.clone(),
+        }
+    }
+}
+
+impl TryFrom<JsonObject> for Object {
+    type Error = TMJError;
+
+    fn try_from(json: JsonObject) -> Result<Self, Self::Error> {
+        Ok(Object {
+            id: json.id,
+            gid: json.gid,
+            coords: [json.x, json.y].into(),
+            size: [json.width, json.height].into(),
+            properties: into_properties(json.properties)?,
+            template: json.template,
+        })
+    }
+}
+
+/// Représentation JSON d'une propriété personnalisée.
+#[derive(Debug, Deserialize, Serialize)]
+pub(crate) struct JsonProperty {
+    name: String,
+    #[serde(default, rename = "type")]
+    property_type: Option<String>,
+    #[serde(
+        default,
+        rename = "propertytype",
+        alias = "propertyType",
+        skip_serializing_if = "Option::is_none"
+    )]
+    custom_type: Option<String>,
+    #[serde(default)]
+    value: Value,
+}
+
+impl JsonProperty {
+    /// Construit la représentation JSON d'une propriété.
+    fn new(name: &str, value: &PropertyValue) -> Self {
+        let custom_type = match value {
+            PropertyValue::Class { property_type, .. }
+            | PropertyValue::Enum { property_type, .. } => Some(property_type.clone()),
+            _ => None,
+        };
+
+        JsonProperty {
+            name: name.to_string(),
+            property_type: Some(String::from(value.type_name())),
+            custom_type,
+            value: json_value(value),
+        }
+    }
+
+    /// Convertit la valeur JSON de la propriété selon son type.
+    fn into_value(self) -> Result<PropertyValue, TMJError> {
+        let invalid = || TMJError::custom(format!("invalid value for property \"{}\"", self.name));
+
+        let value = match self.property_type.as_deref() {
+            None | Some("string") => self.value.as_str().map(|s| PropertyValue::String(s.into())),
+            Some("int") => self.value.as_i64()
+                .and_then(|i| i.try_into().ok())
+                .map(PropertyValue::Int),
+            Some("float") => self.value.as_f64().map(PropertyValue::Float),
+            Some("bool") => self.value.as_bool().map(PropertyValue::Bool),
+            Some("color") => match self.value.as_str() {
+                Some("") => Some(PropertyValue::Color(Color::default())),
+                Some(color) => color.parse().ok().map(PropertyValue::Color),
+                None => None,
+            },
+            Some("file") => self.value.as_str().map(|s| PropertyValue::File(s.into())),
+            Some("object") => self.value.as_u64()
+                .and_then(|id| id.try_into().ok())
+                .map(PropertyValue::Object),
+            Some("class") => match &self.value {
+                Value::Object(members) => Some(class_value(String::new(), members)),
+                Value::Null => Some(class_value(String::new(), &Default::default())),
+                _ => None,
+            },
+            Some(_) => None,
+        };
+
+        Ok(value.ok_or_else(invalid)?.with_custom_type(self.custom_type))
+    }
+}
+
+/// Construit la valeur d'une classe à partir de ses membres.
+///
+/// Le JSON ne précisant pas le type des membres, celui-ci est déduit de leur
+/// valeur.
+fn class_value(property_type: String, members: &serde_json::Map<String, Value>) -> PropertyValue {
+    let properties = members
+        .iter()
+        .filter_map(|(name, value)| {
+            let value = match value {
+                Value::Bool(b) => PropertyValue::Bool(*b),
+                Value::Number(n) => match n.as_i64().and_then(|i| i.try_into().ok()) {
+                    Some(i) => PropertyValue::Int(i),
+                    None => PropertyValue::Float(n.as_f64()?),
+                },
+                Value::String(s) => PropertyValue::String(s.clone()),
+                Value::Object(members) => class_value(String::new(), members),
+                _ => return None,
+            };
+
+            Some((name.clone(), value))
+        })
+        .collect();
+
+    PropertyValue::Class { property_type, properties }
+}
+
+/// Convertit la valeur d'une propriété en valeur JSON.
+///
+/// Les membres d'une classe sont écrits sans leur type, comme le fait Tiled.
+fn json_value(value: &PropertyValue) -> Value {
+    match value {
+        PropertyValue::Bool(b) => Value::from(*b),
+        PropertyValue::Color(color) => Value::from(color.to_string()),
+        PropertyValue::Float(f) => Value::from(*f),
+        PropertyValue::File(s) | PropertyValue::String(s) => Value::from(s.as_str()),
+        PropertyValue::Int(i) => Value::from(*i),
+        PropertyValue::Object(id) => Value::from(*id),
+        PropertyValue::Class { properties, .. } => Value::Object(properties
+            .iter()
+            .map(|(name, value)| (name.clone(), json_value(value)))
+            .collect()),
+        PropertyValue::Enum { value: EnumValue::String(s), .. } => Value::from(s.as_str()),
+        PropertyValue::Enum { value: EnumValue::Int(i), .. } => Value::from(*i),
+    }
+}
+
+/// Convertit des [`Properties`] en liste de propriétés JSON.
+fn from_properties(properties: &Properties) -> Vec<JsonProperty> {
+    properties.iter().map(|(name, value)| JsonProperty::new(name, value)).collect()
+}
+
+/// Convertit une liste de propriétés JSON en [`Properties`].
+pub(crate) fn into_properties(properties: Vec<JsonProperty>) -> Result<Properties, TMJError> {
+    properties
+        .into_iter()
+        .map(|property| Ok((property.name.clone(), property.into_value()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use mint::Vector2;
+
+    use crate::data::{Color, Compression, Encoding, EnumValue, Image, Map, Object, ObjectGroup,
+                      Orientation, Properties, PropertyValue, StaggerAxis, StaggerIndex, Tile,
+                      TileSet, TilesOrigin};
+
+    #[test]
+    fn test_load_tmj_map() {
+        let src = r##"{
+            "type": "map",
+            "orientation": "hexagonal",
+            "staggeraxis": "y",
+            "width": 3,
+            "height": 2,
+            "tilewidth": 24,
+            "tileheight": 12,
+            "tilesets": [
+                { "firstgid": 1, "source": "foo.tsj" },
+                {
+                    "firstgid": 5,
+                    "name": "bar",
+                    "tilewidth": 24,
+                    "tileheight": 12,
+                    "tilecount": 4,
+                    "columns": 2,
+                    "image": "bar.png",
+                    "imagewidth": 48,
+                    "imageheight": 24,
+                    "transparentcolor": "#ff00ff"
+                }
+            ],
+            "layers": [
+                {
+                    "type": "tilelayer",
+                    "id": 1,
+                    "name": "Ground",
+                    "width": 3,
+                    "height": 2,
+                    "encoding": "base64",
+                    "compression": "zlib",
+                    "data": "eJxjYEAAZiBmAmJGIAYAAFAABw=="
+                },
+                {
+                    "type": "objectgroup",
+                    "id": 2,
+                    "name": "Spawns",
+                    "objects": [
+                        { "id": 1, "gid": 5, "x": 10.2, "y": 20, "width": 24, "height": 12 }
+                    ]
+                },
+                { "type": "imagelayer", "id": 3, "image": "sky.png" }
+            ]
+        }"##;
+
+        let mut image = Image::new("bar.png", Vector2 { x: 48, y: 24 });
+        image.trans = Some(Color::rgb(255, 0, 255));
+
+        let mut should_be = Map {
+            size: Vector2 { x: 3, y: 2 },
+            tile_size: Vector2 { x: 24, y: 12 },
+            tiles: vec![0, 0, 0, 3, 2, 1],
+            object_groups: vec![ObjectGroup {
+                id: 2,
+                name: "Spawns".to_string(),
+                objects: vec![Object {
+                    id: 1,
+                    gid: 5,
+                    coords: [10, 20].into(),
+                    size: [24, 12].into(),
+                    properties: Properties::new(),
+                    template: None,
+                }],
+                properties: Properties::new(),
+            }],
+            orientation: Orientation::Hexagonal,
+            stagger_axis: StaggerAxis::YAxis,
+            ..Default::default()
+        };
+
+        should_be.add_tilesets(vec![
+            TileSet {
+                firstgid: 1,
+                source: Some("foo.tsj".to_string()),
+                ..Default::default()
+            },
+            TileSet {
+                firstgid: 5,
+                size: Vector2 { x: 24, y: 12 },
+                count: 4,
+                columns: 2,
+                name: "bar".to_string(),
+                origin: Some(TilesOrigin::Image(image)),
+                ..Default::default()
+            },
+        ]);
+
+        let map = Map::load_tmj(src).unwrap();
+        assert_eq!(map, should_be);
+    }
+
+    #[test]
+    fn test_load_tmj_same_as_tmx() {
+        let tmx = r#"
+        <map orientation="orthogonal" width="3" height="2" tilewidth="24" tileheight="24">
+            <tileset firstgid="1" name="foo" tilewidth="24" tileheight="24" tilecount="4" columns="2">
+                <image source="foo.png" width="48" height="48"/>
+            </tileset>
+            <layer id="1" name="Ground" width="3" height="2">
+                <data encoding="csv">0,0,0,3,2,1</data>
+            </layer>
+        </map>"#;
+
+        let tmj = r#"{
+            "orientation": "orthogonal",
+            "width": 3,
+            "height": 2,
+            "tilewidth": 24,
+            "tileheight": 24,
+            "tilesets": [{
+                "firstgid": 1,
+                "name": "foo",
+                "tilewidth": 24,
+                "tileheight": 24,
+                "tilecount": 4,
+                "columns": 2,
+                "image": "foo.png",
+                "imagewidth": 48,
+                "imageheight": 48
+            }],
+            "layers": [{ "type": "tilelayer", "data": [0, 0, 0, 3, 2, 1] }]
+        }"#;
+
+        assert_eq!(Map::load_tmj(tmj).unwrap(), Map::load_tmx(tmx).unwrap());
+    }
+
+    #[test]
+    fn test_load_tmj_invalid_data() {
+        let src = r#"{
+            "orientation": "orthogonal",
+            "width": 3,
+            "height": 2,
+            "tilewidth": 24,
+            "tileheight": 24,
+            "layers": [{ "type": "tilelayer", "encoding": "base64", "data": "A*A" }]
+        }"#;
+
+        assert!(Map::load_tmj(src).is_err());
+    }
+
+    #[test]
+    fn test_load_tmj_invalid_pixels() {
+        let object = |coords: &str| format!(r#"{{
+            "orientation": "orthogonal",
+            "width": 3,
+            "height": 2,
+            "tilewidth": 24,
+            "tileheight": 24,
+            "layers": [{{ "type": "objectgroup", "objects": [{{ "id": 1, {} }}] }}]
+        }}"#, coords);
+
+        let map = Map::load_tmj(object(r#""x": 65535.2, "y": -0.4"#)).unwrap();
+        assert_eq!(map.object_groups[0].objects[0].coords, [65535, 0].into());
+
+        assert!(Map::load_tmj(object(r#""x": -20.5, "y": 0"#)).is_err());
+        assert!(Map::load_tmj(object(r#""x": 0, "y": 70000"#)).is_err());
+    }
+
+    #[test]
+    fn test_load_tmj_flipped_tiles() {
+        let layer = |data: &str| format!(r#"{{
+            "orientation": "orthogonal",
+            "width": 3,
+            "height": 1,
+            "tilewidth": 24,
+            "tileheight": 24,
+            "layers": [{{ "type": "tilelayer", "width": 3, "height": 1, {} }}]
+        }}"#, data);
+
+        let map = Map::load_tmj(layer(r#""data": [2147483649, 1073741826, 3]"#)).unwrap();
+        assert_eq!(map.tiles, vec![1, 2, 3]);
+
+        let encoded = r#""encoding": "base64", "data": "AQAAgAIAAEADAAAg""#;
+        assert_eq!(Map::load_tmj(layer(encoded)).unwrap().tiles, vec![1, 2, 3]);
+
+        let error = Map::load_tmj(layer(r#""data": [1, 65536, 2]"#)).unwrap_err();
+        assert!(error.to_string().contains("65536"), "{}", error);
+    }
+
+    #[test]
+    fn test_load_tmj_group_layers() {
+        let src = r#"{
+            "orientation": "orthogonal",
+            "width": 2,
+            "height": 1,
+            "tilewidth": 24,
+            "tileheight": 24,
+            "layers": [{
+                "type": "group",
+                "layers": [
+                    { "type": "tilelayer", "id": 2, "width": 2, "height": 1, "data": [1, 2] },
+                    { "type": "group", "layers": [{ "type": "objectgroup", "id": 3 }] }
+                ]
+            }]
+        }"#;
+
+        let map = Map::load_tmj(src).unwrap();
+        assert_eq!(map.tiles, vec![1, 2]);
+        assert_eq!(map.object_groups.len(), 1);
+        assert_eq!(map.object_groups[0].id, 3);
+    }
+
+    #[test]
+    fn test_load_tmj_infinite_map() {
+        let layer = |chunks: &str| format!(r#"{{
+            "orientation": "orthogonal",
+            "infinite": true,
+            "width": 3,
+            "height": 2,
+            "tilewidth": 24,
+            "tileheight": 24,
+            "layers": [{{ "type": "tilelayer", "chunks": [{}] }}]
+        }}"#, chunks);
+
+        let chunks = r#"
+            { "x": 0, "y": 0, "width": 2, "height": 1, "data": [1, 2] },
+            { "x": 1, "y": 1, "width": 2, "height": 1, "data": [3, 4] }
+        "#;
+        assert_eq!(Map::load_tmj(layer(chunks)).unwrap().tiles, vec![1, 2, 0, 0, 3, 4]);
+
+        let outside = r#"{ "x": -1, "y": 0, "width": 2, "height": 1, "data": [5, 0] }"#;
+        let error = Map::load_tmj(layer(outside)).unwrap_err();
+        assert!(error.to_string().contains("(-1, 0)"), "{}", error);
+    }
+
+    #[test]
+    fn test_load_tsj_properties() {
+        let src = r##"{
+            "name": "foo",
+            "properties": [
+                { "name": "color", "type": "color", "value": "#80ff0000" },
+                { "name": "string", "type": "string", "value": "bar" },
+                {
+                    "name": "class",
+                    "type": "class",
+                    "propertytype": "Foo",
+                    "value": { "float": 1.5, "int": 2, "nested": { "bool": true } }
+                }
+            ],
+            "tiles": [
+                { "id": 2, "properties": [{ "name": "cost", "type": "int", "value": 3 }] }
+            ]
+        }"##;
+
+        let mut nested = Properties::new();
+        nested.insert("bool".to_string(), PropertyValue::Bool(true));
+
+        let mut members = Properties::new();
+        members.insert("float".to_string(), PropertyValue::Float(1.5));
+        members.insert("int".to_string(), PropertyValue::Int(2));
+        members.insert("nested".to_string(), PropertyValue::Class {
+            property_type: String::new(),
+            properties: nested,
+        });
+
+        let mut properties = Properties::new();
+        properties.insert("color".to_string(), PropertyValue::Color(Color::rgba(255, 0, 0, 128)));
+        properties.insert("string".to_string(), PropertyValue::String("bar".to_string()));
+        properties.insert("class".to_string(), PropertyValue::Class {
+            property_type: "Foo".to_string(),
+            properties: members,
+        });
+
+        let mut cost = Properties::new();
+        cost.insert("cost".to_string(), PropertyValue::Int(3));
+
+        let tileset = TileSet::load_tsj(src).unwrap();
+        assert_eq!(tileset.properties, properties);
+        assert_eq!(tileset.tile_properties(2), Some(&cost));
+        assert_eq!(tileset.origin, None);
+
+        let invalid = r#"{ "properties": [{ "name": "int", "type": "int", "value": "foo" }] }"#;
+        assert!(TileSet::load_tsj(invalid).is_err());
+    }
+
+    #[test]
+    fn test_load_tsj_tileset() {
+        let src = r#"{
+            "type": "tileset",
+            "name": "foo",
+            "tilewidth": 24,
+            "tileheight": 12,
+            "tilecount": 2,
+            "columns": 0,
+            "tiles": [
+                { "id": 0, "image": "a.png", "imagewidth": 24, "imageheight": 12 },
+                { "id": 3, "image": "b.png", "imagewidth": 24, "imageheight": 12 },
+                { "id": 4 }
+            ]
+        }"#;
+
+        let size = Vector2 { x: 24, y: 12 };
+        let should_be = TileSet {
+            size,
+            count: 2,
+            name: "foo".to_string(),
+            origin: Some(TilesOrigin::new_collection_from(vec![
+                Tile::new(0, Image::new("a.png", size)),
+                Tile::new(3, Image::new("b.png", size)),
+            ])),
+            ..Default::default()
+        };
+
+        let tileset = TileSet::load_tsj(src).unwrap();
+        assert_eq!(tileset, should_be);
+    }
+
+    /// Crée une map dont tous les éléments peuvent être représentés en JSON.
+    fn json_map() -> Map {
+        let mut members = Properties::new();
+        members.insert("bool".to_string(), PropertyValue::Bool(true));
+        members.insert("float".to_string(), PropertyValue::Float(1.5));
+        members.insert("int".to_string(), PropertyValue::Int(-3));
+        members.insert("string".to_string(), PropertyValue::String("a\nb".to_string()));
+
+        let mut properties = members.clone();
+        properties.insert("color".to_string(), PropertyValue::Color(Color::rgba(1, 2, 3, 4)));
+        properties.insert("file".to_string(), PropertyValue::File("foo.txt".to_string()));
+        properties.insert("object".to_string(), PropertyValue::Object(2));
+        properties.insert("enum".to_string(), PropertyValue::Enum {
+            property_type: "Dir".to_string(),
+            value: EnumValue::String("N".to_string()),
+        });
+        properties.insert("flags".to_string(), PropertyValue::Enum {
+            property_type: "Flags".to_string(),
+            value: EnumValue::Int(3),
+        });
+        properties.insert("class".to_string(), PropertyValue::Class {
+            property_type: "Foo".to_string(),
+            properties: members,
+        });
+
+        let mut image = Image::new("image.png", Vector2 { x: 48, y: 24 });
+        image.trans = Some(Color::rgb(255, 0, 255));
+
+        let mut tiles_properties = BTreeMap::new();
+        tiles_properties.insert(1, properties.clone());
+
+        let mut map = Map {
+            size: Vector2 { x: 3, y: 2 },
+            tile_size: Vector2 { x: 24, y: 12 },
+            tiles: vec![0, 1, 2, 3, 9, 10],
+            object_groups: vec![ObjectGroup {
+                id: 2,
+                name: "foo".to_string(),
+                objects: vec![
+                    Object {
+                        id: 1,
+                        gid: 3,
+                        coords: [10, 20].into(),
+                        size: [24, 12].into(),
+                        properties: properties.clone(),
+                        template: None,
+                    },
+                    Object { id: 2, coords: [5, 6].into(), ..Default::default() },
+                ],
+                properties: properties.clone(),
+            }],
+            orientation: Orientation::Hexagonal,
+            stagger_axis: StaggerAxis::XAxis,
+            stagger_index: StaggerIndex::Even,
+            hex_side_length: 6,
+            properties: properties.clone(),
+            ..Default::default()
+        };
+
+        map.add_tilesets(vec![
+            TileSet {
+                firstgid: 1,
+                size: Vector2 { x: 24, y: 12 },
+                count: 4,
+                columns: 2,
+                name: "image".to_string(),
+                origin: Some(TilesOrigin::Image(image)),
+                properties,
+                tiles_properties,
+                ..Default::default()
+            },
+            TileSet {
+                firstgid: 5,
+                source: Some("external.tsj".to_string()),
+                ..Default::default()
+            },
+            TileSet {
+                firstgid: 9,
+                size: Vector2 { x: 24, y: 12 },
+                count: 2,
+                name: "collection".to_string(),
+                origin: Some(TilesOrigin::new_collection_from(vec![
+                    Tile::new(0, Image::new("a.png", Vector2 { x: 24, y: 12 })),
+                    Tile::new(3, Image::new("b.png", Vector2 { x: 24, y: 12 })),
+                ])),
+                ..Default::default()
+            },
+        ]);
+
+        map
+    }
+
+    /// Écrit la map avec l'encodage spécifié, puis vérifie que son chargement
+    /// produit la même map.
+    fn assert_round_trip(map: &Map, encoding: Encoding) {
+        let mut tmj = Vec::new();
+        map.write_tmj(&mut tmj, encoding).unwrap();
+
+        assert_eq!(&Map::load_tmj(&tmj).unwrap(), map, "{}", String::from_utf8_lossy(&tmj));
+    }
+
+    #[test]
+    fn test_write_tmj_round_trip() {
+        let map = json_map();
+
+        assert_round_trip(&map, Encoding::Csv);
+        assert_round_trip(&map, Encoding::Base64(None));
+        assert_round_trip(&map, Encoding::Base64(Some(Compression::Zlib)));
+        assert_round_trip(&map, Encoding::Base64(Some(Compression::Gzip)));
+
+        #[cfg(feature = "zstd")]
+        assert_round_trip(&map, Encoding::Base64(Some(Compression::Zstd)));
+    }
+
+    #[test]
+    fn test_write_tmj_layout() {
+        let mut tmj = Vec::new();
+        json_map().write_tmj(&mut tmj, Encoding::Csv).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&tmj).unwrap();
+        assert_eq!(json["type"], "map");
+        assert_eq!(json["staggeraxis"], "x");
+        assert_eq!(json["staggerindex"], "even");
+        assert_eq!(json["hexsidelength"], 6);
+        assert_eq!(json["nextlayerid"], 3);
+        assert_eq!(json["nextobjectid"], 3);
+        assert_eq!(json["tilesets"][1], serde_json::json!({
+            "firstgid": 5,
+            "source": "external.tsj",
+        }));
+        assert_eq!(json["layers"][0]["type"], "tilelayer");
+        assert_eq!(json["layers"][0]["data"], serde_json::json!([0, 1, 2, 3, 9, 10]));
+        assert_eq!(json["layers"][0]["visible"], true);
+        assert_eq!(json["layers"][1]["type"], "objectgroup");
+        assert_eq!(json["layers"][1]["objects"][1].get("gid"), None);
+
+        // Le calque de tuiles ne reprend pas l'identifiant d'un groupe d'objets.
+        let mut map = json_map();
+        map.object_groups[0].id = 1;
+
+        let mut tmj = Vec::new();
+        map.write_tmj(&mut tmj, Encoding::Csv).unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&tmj).unwrap();
+        assert_eq!(json["layers"][0]["id"], 2);
+        assert_eq!(json["layers"][1]["id"], 1);
+        assert_eq!(json["nextlayerid"], 3);
+
+        let encoding = Encoding::Base64(Some(Compression::Zstd));
+        let result = Map::default().write_tmj(Vec::new(), encoding);
+        assert_eq!(result.is_err(), cfg!(not(feature = "zstd")));
+    }
+
+    #[test]
+    fn test_write_tsj_round_trip() {
+        let mut map = json_map();
+
+        for tileset in map.tilesets_mut().iter_mut().filter(|tileset| tileset.source.is_none()) {
+            tileset.firstgid = TileSet::default().firstgid;
+
+            let mut tsj = Vec::new();
+            tileset.write_tsj(&mut tsj).unwrap();
+
+            let json: serde_json::Value = serde_json::from_slice(&tsj).unwrap();
+            assert_eq!(json["type"], "tileset");
+            assert_eq!(json.get("firstgid"), None);
+
+            assert_eq!(&TileSet::load_tsj(&tsj).unwrap(), tileset);
+        }
+    }
+}
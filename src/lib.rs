@@ -1,5 +1,29 @@
 mod data;
 mod deserial;
+mod diagnostic;
+mod error;
+mod events;
+mod flow;
+mod grid;
+mod hex;
+mod json;
+mod path;
+mod project;
+mod resource;
+mod serial;
+mod validate;
+mod world;
 
 pub use crate::data::*;
-pub use crate::deserial::*;
\ No newline at end of file
+pub use crate::diagnostic::Warning;
+pub use crate::error::{Location, TMXError};
+pub use crate::events::*;
+pub use crate::flow::*;
+pub use crate::grid::*;
+pub use crate::hex::*;
+pub use crate::json::TMJError;
+pub use crate::path::*;
+pub use crate::project::*;
+pub use crate::resource::*;
+pub use crate::validate::*;
+pub use crate::world::*;
\ No newline at end of file
@@ -0,0 +1,482 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f32::consts::SQRT_2;
+
+use mint::Point2;
+
+use crate::data::{EMPTY_TILE, Map, Orientation, PropertyValue, StaggerAxis};
+use crate::grid::{Direction, Neighbor};
+
+/// Coût de déplacement sur les cellules d'une map.
+///
+/// Ce trait est implémenté pour les fonctions prenant en paramètre la map et les
+/// coordonnées d'une cellule.
+pub trait CellCost {
+    /// Renvoie le coût à payer pour entrer dans la cellule passée en paramètre,
+    /// ou `None` si celle-ci est infranchissable. Le coût doit être positif.
+    fn cost(&self, map: &Map, coords: Point2<u16>) -> Option<f32>;
+}
+
+impl<F: Fn(&Map, Point2<u16>) -> Option<f32>> CellCost for F {
+    fn cost(&self, map: &Map, coords: Point2<u16>) -> Option<f32> {
+        self(map, coords)
+    }
+}
+
+/// Coût de déplacement lu dans une propriété des tuiles de la map.
+///
+/// Les cellules vides et celles dont la tuile ne possède pas la propriété coûtent
+/// 1 et sont franchissables.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TilePropertyCost {
+    /// Nom d'une propriété entière ou flottante donnant le coût de la tuile. Un
+    /// coût négatif ou nul rend la tuile infranchissable.
+    Cost(String),
+    /// Nom d'une propriété booléenne rendant la tuile infranchissable lorsqu'elle
+    /// vaut `true`.
+    Solid(String),
+}
+
+impl CellCost for TilePropertyCost {
+    fn cost(&self, map: &Map, coords: Point2<u16>) -> Option<f32> {
+        let gid = map.tiles.get(map.cell_index(coords)).copied().unwrap_or(EMPTY_TILE);
+
+        let property = |name: &str| map.tile_properties(gid).and_then(|props| props.get(name));
+
+        match self {
+            TilePropertyCost::Cost(name) => match property(name) {
+                Some(&PropertyValue::Int(cost)) => Some(cost as f32).filter(|&cost| cost > 0.0),
+                Some(&PropertyValue::Float(cost)) => Some(cost as f32).filter(|&cost| cost > 0.0),
+                _ => Some(1.0),
+            },
+            TilePropertyCost::Solid(name) => match property(name) {
+                Some(PropertyValue::Bool(true)) => None,
+                _ => Some(1.0),
+            },
+        }
+    }
+}
+
+/// Règle appliquée aux déplacements vers une cellule voisine ne partageant qu'un
+/// coin avec la cellule d'origine.
+///
+/// Les deux cellules bordant ce coin sont les voisines partageant un côté avec
+/// la cellule d'origine et avec la cellule d'arrivée. Les maps hexagonales ne
+/// sont pas concernées.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CornerCutting {
+    /// Les déplacements par un coin sont interdits.
+    Never,
+    /// Les déplacements par un coin sont autorisés lorsque les deux cellules
+    /// bordant le coin sont franchissables.
+    #[default]
+    BothOpen,
+    /// Les déplacements par un coin sont autorisés lorsqu'au moins une des deux
+    /// cellules bordant le coin est franchissable.
+    EitherOpen,
+    /// Les déplacements par un coin sont toujours autorisés.
+    Always,
+}
+
+/// Options de la recherche de chemin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathOptions {
+    /// Règle appliquée aux déplacements par un coin.
+    pub corner_cutting: CornerCutting,
+    /// Coût minimal d'une cellule, utilisé pour estimer la distance restante.
+    ///
+    /// S'il dépasse le coût réel d'une cellule, le chemin trouvé peut ne pas
+    /// être le plus court.
+    pub min_cost: f32,
+}
+
+impl Default for PathOptions {
+    fn default() -> Self {
+        Self {
+            corner_cutting: CornerCutting::default(),
+            min_cost: 1.0,
+        }
+    }
+}
+
+/// Chemin renvoyé par [`Map::find_path`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    /// Cellules parcourues, du départ à l'arrivée incluses.
+    pub cells: Vec<Point2<u16>>,
+    /// Coût total du chemin.
+    pub cost: f32,
+}
+
+impl Map {
+    /// Recherche le chemin le moins coûteux entre deux cellules de la map, à
+    /// l'aide de l'algorithme A*.
+    ///
+    /// Les déplacements se font vers les voisines renvoyées par
+    /// [`Map::neighbors`]. Entrer dans une cellule coûte son coût, multiplié par
+    /// √2 lorsque la cellule ne partage qu'un coin avec la précédente. Le coût de
+    /// la cellule de départ n'est pas compté.
+    ///
+    /// Renvoie `None` si l'une des cellules se trouve hors de la map, si
+    /// l'arrivée est infranchissable ou si aucun chemin ne la relie au départ.
+    pub fn find_path<C: CellCost + ?Sized>(
+        &self,
+        start: Point2<u16>,
+        goal: Point2<u16>,
+        cost: &C,
+        options: &PathOptions,
+    ) -> Option<Path> {
+        if !self.contains_cell(start) || !self.contains_cell(goal) {
+            return None;
+        }
+
+        let goal_index = self.cell_index(goal);
+        let mut costs = vec![f32::INFINITY; self.cell_count()];
+        let mut previous = vec![usize::MAX; self.cell_count()];
+        let mut open = BinaryHeap::new();
+
+        costs[self.cell_index(start)] = 0.0;
+        open.push(OpenCell { priority: 0.0, coords: start });
+
+        while let Some(OpenCell { priority, coords }) = open.pop() {
+            let index = self.cell_index(coords);
+
+            if index == goal_index {
+                return Some(self.build_path(start, goal, costs[index], &previous));
+            }
+
+            if priority > costs[index] + self.estimate(coords, goal, options) {
+                continue;
+            }
+
+            for (neighbor, step) in self.steps(coords, cost, options.corner_cutting) {
+                let next = neighbor.coords;
+                let next_index = self.cell_index(next);
+                let next_cost = costs[index] + step;
+
+                if next_cost < costs[next_index] {
+                    costs[next_index] = next_cost;
+                    previous[next_index] = index;
+
+                    let priority = next_cost + self.estimate(next, goal, options);
+                    open.push(OpenCell { priority, coords: next });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Renvoie les voisines franchissables vers lesquelles il est possible de se
+    /// déplacer depuis une cellule, accompagnées du coût de ce déplacement, selon
+    /// le modèle décrit par [`Map::find_path`].
+    ///
+    /// Les déplacements sont symétriques : une cellule est accessible depuis
+    /// chacune des voisines renvoyées si elle-même est franchissable.
+    pub(crate) fn steps<C: CellCost + ?Sized>(
+        &self,
+        coords: Point2<u16>,
+        cost: &C,
+        corner_cutting: CornerCutting,
+    ) -> Vec<(Neighbor, f32)> {
+        let neighbors = self.neighbors(coords);
+        let is_open = |direction: Direction| {
+            neighbors.iter()
+                .find(|neighbor| neighbor.direction == direction)
+                .map_or(false, |neighbor| cost.cost(self, neighbor.coords).is_some())
+        };
+
+        neighbors.iter()
+            .filter(|neighbor| match corner_cutting {
+                _ if neighbor.edge => true,
+                CornerCutting::Never => false,
+                CornerCutting::Always => true,
+                CornerCutting::BothOpen => {
+                    let [left, right] = flanks(neighbor.direction);
+                    is_open(left) && is_open(right)
+                }
+                CornerCutting::EitherOpen => {
+                    let [left, right] = flanks(neighbor.direction);
+                    is_open(left) || is_open(right)
+                }
+            })
+            .filter_map(|&neighbor| {
+                let step = cost.cost(self, neighbor.coords)?;
+                Some((neighbor, step * move_weight(neighbor)))
+            })
+            .collect()
+    }
+
+    /// Estime le coût minimal restant pour rejoindre l'arrivée, sans jamais le
+    /// surestimer tant que [`PathOptions::min_cost`] est respecté.
+    fn estimate(&self, from: Point2<u16>, to: Point2<u16>, options: &PathOptions) -> f32 {
+        let steps = match self.orientation {
+            Orientation::Hexagonal => {
+                let distance = self.to_cube_coords(from).distance(self.to_cube_coords(to));
+                return distance as f32 * options.min_cost;
+            }
+            Orientation::Orthogonal | Orientation::Isometric => [
+                (i32::from(from.x) - i32::from(to.x)).unsigned_abs(),
+                (i32::from(from.y) - i32::from(to.y)).unsigned_abs(),
+            ],
+            // Une map décalée est une map isométrique dont les axes s'obtiennent à
+            // partir des coordonnées cubiques.
+            Orientation::Staggered => {
+                let offset = self.to_cube_coords(from) - self.to_cube_coords(to);
+                let first = match self.staggered_axis() {
+                    StaggerAxis::XAxis => offset.r,
+                    _ => offset.q,
+                };
+                [first.unsigned_abs(), offset.s.unsigned_abs()]
+            }
+        };
+
+        let long = steps[0].max(steps[1]) as f32;
+        let short = steps[0].min(steps[1]) as f32;
+
+        let distance = match options.corner_cutting {
+            CornerCutting::Never => long + short,
+            _ => long + (SQRT_2 - 1.0) * short,
+        };
+
+        distance * options.min_cost
+    }
+
+    /// Reconstruit le chemin menant à l'arrivée.
+    fn build_path(
+        &self,
+        start: Point2<u16>,
+        goal: Point2<u16>,
+        cost: f32,
+        previous: &[usize],
+    ) -> Path {
+        let mut cells = vec![goal];
+        let mut index = self.cell_index(goal);
+
+        while index != self.cell_index(start) {
+            index = previous[index];
+            cells.push(self.cell_coords(index));
+        }
+
+        cells.reverse();
+        Path { cells, cost }
+    }
+
+    /// Indique si la cellule passée en paramètre se trouve dans la map.
+    #[inline]
+    pub(crate) fn contains_cell(&self, coords: Point2<u16>) -> bool {
+        coords.x < self.size.x && coords.y < self.size.y
+    }
+
+    /// Renvoie le nombre de cellules de la map.
+    #[inline]
+    pub(crate) fn cell_count(&self) -> usize {
+        usize::from(self.size.x) * usize::from(self.size.y)
+    }
+
+    /// Renvoie l'indice d'une cellule, dans l'ordre des tuiles de la map.
+    #[inline]
+    pub(crate) fn cell_index(&self, coords: Point2<u16>) -> usize {
+        usize::from(coords.y) * usize::from(self.size.x) + usize::from(coords.x)
+    }
+
+    /// Renvoie les coordonnées de la cellule d'indice passé en paramètre.
+    #[inline]
+    pub(crate) fn cell_coords(&self, index: usize) -> Point2<u16> {
+        let width = usize::from(self.size.x);
+        Point2 { x: (index % width) as u16, y: (index / width) as u16 }
+    }
+}
+
+/// Renvoie les directions des deux cellules bordant le coin partagé avec la
+/// voisine située dans la direction passée en paramètre.
+fn flanks(direction: Direction) -> [Direction; 2] {
+    use Direction::*;
+
+    match direction {
+        North => [NorthWest, NorthEast],
+        NorthEast => [North, East],
+        East => [NorthEast, SouthEast],
+        SouthEast => [East, South],
+        South => [SouthEast, SouthWest],
+        SouthWest => [South, West],
+        West => [SouthWest, NorthWest],
+        NorthWest => [West, North],
+    }
+}
+
+/// Renvoie le facteur appliqué au coût d'une cellule lorsqu'on y entre depuis la
+/// voisine passée en paramètre.
+#[inline]
+pub(crate) fn move_weight(neighbor: Neighbor) -> f32 {
+    if neighbor.edge { 1.0 } else { SQRT_2 }
+}
+
+/// Cellule en attente d'exploration, ordonnée par priorité croissante.
+pub(crate) struct OpenCell {
+    pub(crate) priority: f32,
+    pub(crate) coords: Point2<u16>,
+}
+
+impl PartialEq for OpenCell {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenCell {}
+
+impl PartialOrd for OpenCell {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenCell {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.total_cmp(&self.priority)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use mint::Vector2;
+
+    use crate::data::{Properties, StaggerIndex, TileSet};
+
+    /// Crée une map dont les cellules marquées `#` sont infranchissables.
+    fn maze(orientation: Orientation, rows: &[&str]) -> Map {
+        let mut map = Map {
+            orientation,
+            size: Vector2 { x: rows[0].len() as u16, y: rows.len() as u16 },
+            tile_size: Vector2 { x: 16, y: 16 },
+            tiles: rows.concat().bytes().map(|cell| if cell == b'#' { 2 } else { 1 }).collect(),
+            ..Default::default()
+        };
+
+        let solid = Properties::from([(String::from("solid"), PropertyValue::Bool(true))]);
+        map.add_tileset(TileSet {
+            firstgid: 1,
+            count: 2,
+            tiles_properties: BTreeMap::from([(1, solid)]),
+            ..Default::default()
+        });
+
+        map
+    }
+
+    fn cells(path: &Path) -> Vec<[u16; 2]> {
+        path.cells.iter().map(|&coords| coords.into()).collect()
+    }
+
+    #[test]
+    fn test_find_path() {
+        let map = maze(Orientation::Orthogonal, &[
+            "....",
+            ".##.",
+            "..#.",
+        ]);
+        let solid = TilePropertyCost::Solid(String::from("solid"));
+        let options = PathOptions::default();
+
+        // Les coins bordés par un obstacle ne peuvent pas être coupés.
+        let path = map.find_path([0, 2].into(), [3, 2].into(), &solid, &options).unwrap();
+        assert_eq!(path.cells.len(), 8);
+        assert_eq!(path.cost, 7.0);
+
+        let never = PathOptions { corner_cutting: CornerCutting::Never, ..options };
+        let path = map.find_path([0, 2].into(), [3, 2].into(), &solid, &never).unwrap();
+        assert_eq!(path.cost, 7.0);
+
+        let either = PathOptions { corner_cutting: CornerCutting::EitherOpen, ..options };
+        let path = map.find_path([0, 2].into(), [3, 2].into(), &solid, &either).unwrap();
+        assert_eq!(cells(&path), vec![[0, 2], [0, 1], [1, 0], [2, 0], [3, 1], [3, 2]]);
+        assert!((path.cost - (3.0 + 2.0 * SQRT_2)).abs() < 1e-5);
+
+        let gap = maze(Orientation::Orthogonal, &["#.", ".#"]);
+        let always = PathOptions { corner_cutting: CornerCutting::Always, ..options };
+        assert_eq!(gap.find_path([0, 1].into(), [1, 0].into(), &solid, &either), None);
+        let path = gap.find_path([0, 1].into(), [1, 0].into(), &solid, &always).unwrap();
+        assert_eq!(cells(&path), vec![[0, 1], [1, 0]]);
+
+        let open = maze(Orientation::Orthogonal, &["...", "...", "..."]);
+        let path = open.find_path([0, 0].into(), [2, 2].into(), &solid, &options).unwrap();
+        assert_eq!(cells(&path), vec![[0, 0], [1, 1], [2, 2]]);
+
+        assert_eq!(map.find_path([0, 0].into(), [1, 1].into(), &solid, &options), None);
+        assert_eq!(map.find_path([0, 0].into(), [4, 0].into(), &solid, &options), None);
+
+        let path = map.find_path([3, 0].into(), [3, 0].into(), &solid, &options).unwrap();
+        assert_eq!(path, Path { cells: vec![[3, 0].into()], cost: 0.0 });
+    }
+
+    #[test]
+    fn test_find_path_costs() {
+        let mut map = maze(Orientation::Orthogonal, &[
+            "...",
+            "...",
+            "...",
+        ]);
+        map.tiles[4] = 2;
+        map.tilesets_mut()[0].tiles_properties.insert(1, Properties::from([
+            (String::from("cost"), PropertyValue::Float(5.0)),
+        ]));
+
+        let cost = TilePropertyCost::Cost(String::from("cost"));
+        let options = PathOptions { corner_cutting: CornerCutting::Never, ..Default::default() };
+
+        let path = map.find_path([0, 1].into(), [2, 1].into(), &cost, &options).unwrap();
+        assert_eq!(path.cost, 4.0);
+        assert!(!path.cells.contains(&[1, 1].into()));
+
+        let flat = |_: &Map, _: Point2<u16>| Some(1.0);
+        let path = map.find_path([0, 1].into(), [2, 1].into(), &flat, &options).unwrap();
+        assert_eq!(cells(&path), vec![[0, 1], [1, 1], [2, 1]]);
+    }
+
+    #[test]
+    fn test_find_path_orientations() {
+        let rows = ["......", ".#.#..", "......", "..#.#.", "......", ".#...."];
+        let solid = TilePropertyCost::Solid(String::from("solid"));
+
+        let orientations = [
+            Orientation::Orthogonal,
+            Orientation::Isometric,
+            Orientation::Staggered,
+            Orientation::Hexagonal,
+        ];
+
+        for &orientation in &orientations {
+            for &axis in &[StaggerAxis::XAxis, StaggerAxis::YAxis] {
+                for &index in &[StaggerIndex::Odd, StaggerIndex::Even] {
+                    let mut map = maze(orientation, &rows);
+                    map.stagger_axis = axis;
+                    map.stagger_index = index;
+
+                    for &corner_cutting in &[CornerCutting::Never, CornerCutting::Always] {
+                        let options = PathOptions { corner_cutting, ..Default::default() };
+                        let path = map.find_path([0, 0].into(), [5, 5].into(), &solid, &options)
+                            .unwrap();
+
+                        // Chaque pas mène à une voisine franchissable.
+                        for pair in path.cells.windows(2) {
+                            let step = map.steps(pair[0], &solid, corner_cutting)
+                                .into_iter()
+                                .find(|(neighbor, _)| neighbor.coords == pair[1]);
+                            assert!(step.is_some(), "{:?} {:?}", orientation, pair);
+                        }
+
+                        // Le chemin trouvé est aussi court qu'une recherche exhaustive.
+                        let zero = PathOptions { min_cost: 0.0, ..options };
+                        let best = map.find_path([0, 0].into(), [5, 5].into(), &solid, &zero)
+                            .unwrap();
+                        assert!((path.cost - best.cost).abs() < 1e-4, "{:?}", orientation);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,642 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::mem;
+
+use serde::Deserialize;
+
+use crate::data::{Color, EnumValue, Map, ParsingError, Properties, PropertyValue, TileSet};
+use crate::json::{into_properties, JsonProperty, TMJError};
+
+/// Profondeur maximale d'imbrication des classes, au-delà de laquelle la
+/// définition des types est considérée comme récursive.
+const MAX_CLASS_DEPTH: usize = 64;
+
+/// Contient les données d'un fichier de projet `.tiled-project`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Project {
+    /// Dossiers faisant partie du projet, relatifs au fichier de projet.
+    pub folders: Vec<String>,
+    /// Commandes pouvant être exécutées depuis Tiled.
+    pub commands: Vec<Command>,
+    /// Types de propriétés personnalisés, indexés par leur nom.
+    pub property_types: BTreeMap<String, PropertyType>,
+}
+
+impl Project {
+    /// Instancie un nouveau projet depuis les données d'un fichier
+    /// `.tiled-project`.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si les données passées en paramètre ne
+    /// sont pas au format JSON ou ne décrivent pas un projet.
+    pub fn load<D: AsRef<[u8]>>(data: D) -> Result<Self, TMJError> {
+        Project::try_from(serde_json::from_slice::<JsonProject>(data.as_ref())?)
+    }
+
+    /// Renvoie le type de propriété personnalisé portant le nom passé en
+    /// paramètre.
+    #[inline]
+    pub fn property_type(&self, name: &str) -> Option<&PropertyType> {
+        self.property_types.get(name)
+    }
+
+    /// Renvoie la classe portant le nom passé en paramètre.
+    pub fn class_type(&self, name: &str) -> Option<&ClassType> {
+        match self.property_type(name) {
+            Some(PropertyType::Class(class)) => Some(class),
+            _ => None,
+        }
+    }
+
+    /// Renvoie l'énumération portant le nom passé en paramètre.
+    pub fn enum_type(&self, name: &str) -> Option<&EnumType> {
+        match self.property_type(name) {
+            Some(PropertyType::Enum(enum_type)) => Some(enum_type),
+            _ => None,
+        }
+    }
+
+    /// Résout les propriétés de la map, de ses jeux de tuiles, de ses groupes
+    /// d'objets et de ses objets.
+    ///
+    /// # Erreurs
+    ///
+    /// Voir [`Project::resolve_properties`].
+    pub fn resolve_map(&self, map: &mut Map) -> Result<(), ParsingError> {
+        self.resolve_properties(&mut map.properties)?;
+
+        for tileset in map.tilesets_mut() {
+            self.resolve_tileset(tileset)?;
+        }
+
+        for group in &mut map.object_groups {
+            self.resolve_properties(&mut group.properties)?;
+
+            for object in &mut group.objects {
+                self.resolve_properties(&mut object.properties)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Résout les propriétés du jeu de tuiles ainsi que celles de ses tuiles.
+    ///
+    /// # Erreurs
+    ///
+    /// Voir [`Project::resolve_properties`].
+    pub fn resolve_tileset(&self, tileset: &mut TileSet) -> Result<(), ParsingError> {
+        self.resolve_properties(&mut tileset.properties)?;
+
+        for properties in tileset.tiles_properties.values_mut() {
+            self.resolve_properties(properties)?;
+        }
+
+        Ok(())
+    }
+
+    /// Résout les propriétés à l'aide des types personnalisés du projet.
+    ///
+    /// Les membres absents d'une classe prennent leur valeur par défaut, et les
+    /// membres dont le type a été déduit de leur valeur prennent le type défini
+    /// par la classe. Les valeurs des énumérations sont vérifiées.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si une propriété utilise un type
+    /// inconnu du projet, si la valeur d'un membre ne correspond pas à son type,
+    /// ou si la valeur d'une énumération n'en fait pas partie.
+    pub fn resolve_properties(&self, properties: &mut Properties) -> Result<(), ParsingError> {
+        properties.values_mut().try_for_each(|value| self.resolve_property(value))
+    }
+
+    /// Résout la valeur d'une propriété à l'aide des types personnalisés du
+    /// projet.
+    ///
+    /// # Erreurs
+    ///
+    /// Voir [`Project::resolve_properties`].
+    #[inline]
+    pub(crate) fn resolve_property(&self, value: &mut PropertyValue) -> Result<(), ParsingError> {
+        self.resolve_value(value, 0)
+    }
+
+    /// Résout une valeur, `depth` étant sa profondeur d'imbrication.
+    fn resolve_value(&self, value: &mut PropertyValue, depth: usize) -> Result<(), ParsingError> {
+        match value {
+            PropertyValue::Class { property_type, properties } => {
+                let class = self.class_type(property_type)
+                    .ok_or_else(|| ParsingError::InvalidString(property_type.clone()))?;
+
+                if depth >= MAX_CLASS_DEPTH {
+                    return Err(ParsingError::InvalidString(property_type.clone()));
+                }
+
+                for (name, default) in &class.members {
+                    let mut member = match properties.remove(name) {
+                        Some(member) => coerce(member, default)?,
+                        None => default.clone(),
+                    };
+
+                    self.resolve_value(&mut member, depth + 1)?;
+                    properties.insert(name.clone(), member);
+                }
+
+                Ok(())
+            }
+            PropertyValue::Enum { property_type, value } => {
+                let enum_type = self.enum_type(property_type)
+                    .ok_or_else(|| ParsingError::InvalidString(property_type.clone()))?;
+
+                match enum_type.names(value) {
+                    Some(_) => Ok(()),
+                    None => {
+                        let message = format!("{}: {:?}", property_type, value);
+                        Err(ParsingError::InvalidString(message))
+                    }
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Convertit la valeur d'un membre dans le type de sa valeur par défaut.
+///
+/// Le type des membres n'étant pas écrit dans les fichiers JSON, celui-ci a pu
+/// être déduit de la valeur lors du chargement.
+fn coerce(value: PropertyValue, default: &PropertyValue) -> Result<PropertyValue, ParsingError> {
+    let value = match (value, default) {
+        (PropertyValue::Class { properties, .. }, PropertyValue::Class { property_type, .. }) => {
+            PropertyValue::Class { property_type: property_type.clone(), properties }
+        }
+        (value, default) if mem::discriminant(&value) == mem::discriminant(default) => value,
+        (PropertyValue::Int(i), PropertyValue::Float(_)) => PropertyValue::Float(f64::from(i)),
+        (PropertyValue::Int(i), PropertyValue::Object(_)) => u16::try_from(i)
+            .map(PropertyValue::Object)
+            .map_err(|_| ParsingError::InvalidString(i.to_string()))?,
+        (PropertyValue::String(s), PropertyValue::Color(_)) if s.is_empty() => {
+            PropertyValue::Color(Color::default())
+        }
+        (PropertyValue::String(s), PropertyValue::Color(_)) => PropertyValue::Color(s.parse()?),
+        (PropertyValue::String(s), PropertyValue::File(_)) => PropertyValue::File(s),
+        (PropertyValue::String(s), PropertyValue::Enum { property_type, .. }) => {
+            let property_type = property_type.clone();
+            PropertyValue::Enum { property_type, value: EnumValue::String(s) }
+        }
+        (PropertyValue::Int(i), PropertyValue::Enum { property_type, .. }) => {
+            let property_type = property_type.clone();
+            PropertyValue::Enum { property_type, value: EnumValue::Int(i) }
+        }
+        (value, default) => {
+            let message = format!("expected {}, found {:?}", default.type_name(), value);
+            return Err(ParsingError::InvalidString(message));
+        }
+    };
+
+    Ok(value)
+}
+
+/// Commande pouvant être exécutée depuis Tiled.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct Command {
+    /// Nom de la commande.
+    pub name: String,
+    /// Exécutable lancé par la commande.
+    pub command: String,
+    /// Arguments passés à l'exécutable.
+    pub arguments: String,
+    /// Dossier dans lequel est exécutée la commande.
+    pub working_directory: String,
+    /// Raccourci clavier associé à la commande.
+    pub shortcut: String,
+    /// Indique si la commande est activée.
+    pub enabled: bool,
+    /// Indique si la sortie de la commande est affichée dans la console.
+    pub show_output: bool,
+    /// Indique si la map est enregistrée avant l'exécution de la commande.
+    pub save_before_execute: bool,
+}
+
+/// Représente un type de propriété personnalisé défini par un projet.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PropertyType {
+    /// Énumération.
+    Enum(EnumType),
+    /// Classe.
+    Class(ClassType),
+}
+
+impl PropertyType {
+    /// Renvoie le nom du type.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Enum(enum_type) => &enum_type.name,
+            Self::Class(class) => &class.name,
+        }
+    }
+}
+
+/// Représente le mode de stockage des valeurs d'une énumération.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnumStorage {
+    /// Les valeurs sont stockées par leur nom.
+    #[default]
+    String,
+    /// Les valeurs sont stockées par leur indice.
+    Int,
+}
+
+/// Énumération personnalisée définie par un projet.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnumType {
+    /// Identifiant du type dans le projet.
+    #[serde(default)]
+    pub id: u32,
+    /// Nom de l'énumération.
+    pub name: String,
+    /// Mode de stockage des valeurs.
+    #[serde(default)]
+    pub storage_type: EnumStorage,
+    /// Noms des valeurs de l'énumération.
+    #[serde(default)]
+    pub values: Vec<String>,
+    /// Indique si plusieurs valeurs peuvent être combinées.
+    #[serde(default)]
+    pub values_as_flags: bool,
+}
+
+impl EnumType {
+    /// Renvoie les noms des valeurs représentées par la valeur stockée passée en
+    /// paramètre, ou `None` si celle-ci ne correspond pas à l'énumération.
+    pub fn names(&self, value: &EnumValue) -> Option<Vec<&str>> {
+        match value {
+            EnumValue::String(s) if self.values_as_flags => s
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(|name| self.value(name))
+                .collect(),
+            EnumValue::String(s) => self.value(s).map(|name| vec![name]),
+            EnumValue::Int(i) if self.values_as_flags => {
+                let flags = u32::try_from(*i).ok()?;
+                let count = u32::try_from(self.values.len()).unwrap_or(u32::MAX);
+
+                if flags.checked_shr(count).unwrap_or(0) != 0 {
+                    return None;
+                }
+
+                let names = self.values
+                    .iter()
+                    .take(32)
+                    .enumerate()
+                    .filter(|(bit, _)| flags & (1 << bit) != 0)
+                    .map(|(_, name)| name.as_str())
+                    .collect();
+
+                Some(names)
+            }
+            EnumValue::Int(i) => {
+                let name = self.values.get(usize::try_from(*i).ok()?)?;
+                Some(vec![name.as_str()])
+            }
+        }
+    }
+
+    /// Renvoie la valeur de l'énumération portant le nom passé en paramètre.
+    fn value(&self, name: &str) -> Option<&str> {
+        self.values.iter().find(|value| *value == name).map(String::as_str)
+    }
+}
+
+/// Classe personnalisée définie par un projet.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClassType {
+    /// Identifiant du type dans le projet.
+    pub id: u32,
+    /// Nom de la classe.
+    pub name: String,
+    /// Couleur associée à la classe dans Tiled.
+    pub color: Option<Color>,
+    /// Éléments pouvant être instances de la classe (`property`, `map`,
+    /// `layer`, `object`, `tile`, `tileset`…).
+    pub use_as: Vec<String>,
+    /// Valeurs par défaut des membres de la classe.
+    pub members: Properties,
+}
+
+/// Représentation JSON d'un projet.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonProject {
+    #[serde(default)]
+    folders: Vec<String>,
+    #[serde(default)]
+    commands: Vec<Command>,
+    #[serde(default)]
+    property_types: Vec<JsonPropertyType>,
+}
+
+impl TryFrom<JsonProject> for Project {
+    type Error = TMJError;
+
+    fn try_from(json: JsonProject) -> Result<Self, Self::Error> {
+        let property_types = json.property_types
+            .into_iter()
+            .map(|property_type| {
+                let property_type = match property_type {
+                    JsonPropertyType::Enum(enum_type) => PropertyType::Enum(enum_type),
+                    JsonPropertyType::Class(class) => PropertyType::Class(ClassType {
+                        id: class.id,
+                        name: class.name,
+                        color: class.color,
+                        use_as: class.use_as,
+                        members: into_properties(class.members)?,
+                    }),
+                };
+
+                Ok((property_type.name().to_string(), property_type))
+            })
+            .collect::<Result<_, TMJError>>()?;
+
+        Ok(Project {
+            folders: json.folders,
+            commands: json.commands,
+            property_types,
+        })
+    }
+}
+
+/// Représentation JSON d'un type de propriété personnalisé.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum JsonPropertyType {
+    Enum(EnumType),
+    Class(JsonClassType),
+}
+
+/// Représentation JSON d'une classe personnalisée.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonClassType {
+    #[serde(default)]
+    id: u32,
+    name: String,
+    #[serde(default)]
+    color: Option<Color>,
+    #[serde(default)]
+    use_as: Vec<String>,
+    #[serde(default)]
+    members: Vec<JsonProperty>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::LoadOptions;
+    use crate::error::TMXError;
+
+    const PROJECT: &str = r##"{
+        "automappingRulesFile": "",
+        "commands": [
+            {
+                "arguments": "%mapfile",
+                "command": "game",
+                "enabled": true,
+                "name": "Run",
+                "saveBeforeExecute": true,
+                "shortcut": "F5",
+                "showOutput": false,
+                "workingDirectory": "."
+            }
+        ],
+        "extensionsPath": "extensions",
+        "folders": ["maps", "tilesets"],
+        "propertyTypes": [
+            {
+                "id": 1,
+                "name": "Dir",
+                "type": "enum",
+                "storageType": "string",
+                "values": ["N", "E", "S", "W"],
+                "valuesAsFlags": false
+            },
+            {
+                "id": 2,
+                "name": "Flags",
+                "type": "enum",
+                "storageType": "int",
+                "values": ["A", "B", "C"],
+                "valuesAsFlags": true
+            },
+            {
+                "id": 3,
+                "name": "Point",
+                "type": "class",
+                "useAs": ["property"],
+                "members": [
+                    { "name": "x", "type": "float", "value": 0 },
+                    { "name": "y", "type": "float", "value": 0 }
+                ]
+            },
+            {
+                "id": 4,
+                "name": "Unit",
+                "type": "class",
+                "color": "#ffa0a0a4",
+                "useAs": ["property", "object"],
+                "members": [
+                    { "name": "dir", "type": "string", "propertyType": "Dir", "value": "N" },
+                    { "name": "hp", "type": "int", "value": 10 },
+                    {
+                        "name": "pos",
+                        "type": "class",
+                        "propertyType": "Point",
+                        "value": { "x": 1 }
+                    },
+                    { "name": "tint", "type": "color", "value": "" }
+                ]
+            }
+        ]
+    }"##;
+
+    /// Crée la valeur d'une énumération stockée par son nom.
+    fn enum_value(property_type: &str, value: &str) -> PropertyValue {
+        PropertyValue::Enum {
+            property_type: property_type.to_string(),
+            value: EnumValue::String(value.to_string()),
+        }
+    }
+
+    /// Crée la valeur d'une instance de la classe `Point`.
+    fn point(x: f64, y: f64) -> PropertyValue {
+        let mut properties = Properties::new();
+        properties.insert("x".to_string(), PropertyValue::Float(x));
+        properties.insert("y".to_string(), PropertyValue::Float(y));
+
+        PropertyValue::Class { property_type: "Point".to_string(), properties }
+    }
+
+    #[test]
+    fn test_load_project() {
+        let project = Project::load(PROJECT).unwrap();
+
+        assert_eq!(project.folders, vec!["maps", "tilesets"]);
+        assert_eq!(project.commands, vec![Command {
+            name: "Run".to_string(),
+            command: "game".to_string(),
+            arguments: "%mapfile".to_string(),
+            working_directory: ".".to_string(),
+            shortcut: "F5".to_string(),
+            enabled: true,
+            show_output: false,
+            save_before_execute: true,
+        }]);
+
+        assert_eq!(project.enum_type("Flags"), Some(&EnumType {
+            id: 2,
+            name: "Flags".to_string(),
+            storage_type: EnumStorage::Int,
+            values: vec!["A".to_string(), "B".to_string(), "C".to_string()],
+            values_as_flags: true,
+        }));
+
+        let unit = project.class_type("Unit").unwrap();
+        assert_eq!(unit.color, Some(Color::rgba(160, 160, 164, 255)));
+        assert_eq!(unit.use_as, vec!["property", "object"]);
+        assert_eq!(unit.members["dir"], enum_value("Dir", "N"));
+        assert_eq!(unit.members["hp"], PropertyValue::Int(10));
+
+        assert_eq!(project.class_type("Dir"), None);
+        let unknown_kind = r#"{ "propertyTypes": [{ "type": "foo", "name": "Foo" }] }"#;
+        assert!(Project::load(unknown_kind).is_err());
+    }
+
+    #[test]
+    fn test_enum_names() {
+        let project = Project::load(PROJECT).unwrap();
+
+        let dir = project.enum_type("Dir").unwrap();
+        assert_eq!(dir.names(&EnumValue::String("S".to_string())), Some(vec!["S"]));
+        assert_eq!(dir.names(&EnumValue::String("X".to_string())), None);
+        assert_eq!(dir.names(&EnumValue::Int(1)), Some(vec!["E"]));
+        assert_eq!(dir.names(&EnumValue::Int(4)), None);
+
+        let flags = project.enum_type("Flags").unwrap();
+        assert_eq!(flags.names(&EnumValue::Int(0b101)), Some(vec!["A", "C"]));
+        assert_eq!(flags.names(&EnumValue::Int(0b1000)), None);
+        assert_eq!(flags.names(&EnumValue::Int(-1)), None);
+        assert_eq!(flags.names(&EnumValue::String("A,B".to_string())), Some(vec!["A", "B"]));
+        assert_eq!(flags.names(&EnumValue::String(String::new())), Some(vec![]));
+        assert_eq!(flags.names(&EnumValue::String("A,D".to_string())), None);
+    }
+
+    #[test]
+    fn test_resolve_properties() {
+        let project = Project::load(PROJECT).unwrap();
+
+        let mut members = Properties::new();
+        members.insert("hp".to_string(), PropertyValue::Int(3));
+        members.insert("dir".to_string(), PropertyValue::String("W".to_string()));
+
+        let mut properties = Properties::new();
+        properties.insert("unit".to_string(), PropertyValue::Class {
+            property_type: "Unit".to_string(),
+            properties: members,
+        });
+        properties.insert("dir".to_string(), enum_value("Dir", "E"));
+        properties.insert("name".to_string(), PropertyValue::String("foo".to_string()));
+
+        project.resolve_properties(&mut properties).unwrap();
+
+        let mut members = Properties::new();
+        members.insert("dir".to_string(), enum_value("Dir", "W"));
+        members.insert("hp".to_string(), PropertyValue::Int(3));
+        members.insert("pos".to_string(), point(1.0, 0.0));
+        members.insert("tint".to_string(), PropertyValue::Color(Color::default()));
+
+        assert_eq!(properties["unit"], PropertyValue::Class {
+            property_type: "Unit".to_string(),
+            properties: members,
+        });
+        assert_eq!(properties["dir"], enum_value("Dir", "E"));
+        assert_eq!(properties["name"], PropertyValue::String("foo".to_string()));
+
+        let mut invalid = Properties::new();
+        invalid.insert("dir".to_string(), enum_value("Dir", "X"));
+        assert!(project.resolve_properties(&mut invalid).is_err());
+
+        let mut unknown = Properties::new();
+        unknown.insert("dir".to_string(), enum_value("Foo", "N"));
+        assert!(project.resolve_properties(&mut unknown).is_err());
+
+        let mut members = Properties::new();
+        members.insert("hp".to_string(), PropertyValue::Bool(true));
+
+        let mut mismatch = Properties::new();
+        mismatch.insert("unit".to_string(), PropertyValue::Class {
+            property_type: "Unit".to_string(),
+            properties: members,
+        });
+        assert!(project.resolve_properties(&mut mismatch).is_err());
+    }
+
+    #[test]
+    fn test_load_map_with_project() {
+        let project = Project::load(PROJECT).unwrap();
+
+        let tmx = r#"
+        <map orientation="orthogonal" width="1" height="1" tilewidth="24" tileheight="24">
+            <properties>
+                <property name="dir" propertytype="Dir" value="S"/>
+                <property name="flags" type="int" propertytype="Flags" value="3"/>
+                <property name="pos" type="class" propertytype="Point">
+                    <properties>
+                        <property name="y" type="float" value="2"/>
+                    </properties>
+                </property>
+            </properties>
+        </map>"#;
+
+        let tmj = r#"{
+            "orientation": "orthogonal",
+            "width": 1,
+            "height": 1,
+            "tilewidth": 24,
+            "tileheight": 24,
+            "properties": [
+                { "name": "dir", "type": "string", "propertytype": "Dir", "value": "S" },
+                { "name": "flags", "type": "int", "propertytype": "Flags", "value": 3 },
+                { "name": "pos", "type": "class", "propertytype": "Point", "value": { "y": 2 } }
+            ]
+        }"#;
+
+        let mut properties = Properties::new();
+        properties.insert("dir".to_string(), enum_value("Dir", "S"));
+        properties.insert("flags".to_string(), PropertyValue::Enum {
+            property_type: "Flags".to_string(),
+            value: EnumValue::Int(3),
+        });
+        properties.insert("pos".to_string(), point(0.0, 2.0));
+
+        let options = LoadOptions { project: Some(&project), ..LoadOptions::default() };
+        let (map, _) = Map::load_tmx_with(tmx.as_bytes(), &options).unwrap();
+        assert_eq!(map.properties, properties);
+
+        let map = Map::load_tmj_with(tmj.as_bytes(), &options).unwrap();
+        assert_eq!(map.properties, properties);
+
+        let invalid = tmx.replace("value=\"S\"", "value=\"X\"");
+        match Map::load_tmx_with(invalid.as_bytes(), &options).unwrap_err() {
+            TMXError::Attribute { location, name, .. } => {
+                assert_eq!(name, "dir");
+                assert_eq!(location.path, "map/properties/property[name=dir]");
+                assert_eq!(location.line, 4);
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+    }
+}
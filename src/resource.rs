@@ -0,0 +1,313 @@
+#[cfg(feature = "zip")]
+use std::cell::RefCell;
+use std::collections::HashMap;
+#[cfg(feature = "zip")]
+use std::convert::TryFrom;
+use std::fs::File;
+#[cfg(feature = "zip")]
+use std::io::Seek;
+use std::io::{self, Cursor, Read};
+#[cfg(feature = "zip")]
+use std::path::Component;
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "zip")]
+use zip::ZipArchive;
+#[cfg(feature = "zip")]
+use zip::result::ZipError;
+
+/// Source des fichiers lus lors du chargement d'une map : la map elle-même, ses
+/// jeux de tuiles externes, ses modèles d'objets et ses images.
+///
+/// Les chemins d'accès passés à [`ResourceReader::open`] sont ceux des fichiers
+/// référencés, résolus par rapport au fichier les référençant et débarrassés
+/// de leurs composantes `.` et `..` superflues.
+pub trait ResourceReader {
+    /// Ouvre en lecture le fichier situé au chemin d'accès passé en paramètre.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si le fichier n'existe pas ou ne peut
+    /// être ouvert.
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>>;
+}
+
+/// Lit les fichiers depuis le système de fichiers.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FileSystem;
+
+impl ResourceReader for FileSystem {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        Ok(Box::new(File::open(path)?))
+    }
+}
+
+/// Lit les fichiers depuis la mémoire, chaque fichier étant indexé par son chemin
+/// d'accès.
+impl ResourceReader for HashMap<PathBuf, Vec<u8>> {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        match self.get(path) {
+            Some(data) => Ok(Box::new(Cursor::new(data.as_slice()))),
+            None => {
+                let message = format!("no such file: {}", path.display());
+                Err(io::Error::new(io::ErrorKind::NotFound, message))
+            }
+        }
+    }
+}
+
+/// Lit les fichiers depuis une archive `.zip`, disponible avec la fonctionnalité
+/// `zip`.
+///
+/// Les chemins d'accès sont relatifs à la racine de l'archive, et les fichiers
+/// sont résolus comme s'ils avaient été extraits sur le disque.
+#[cfg(feature = "zip")]
+pub struct ZipReader<R> {
+    archive: RefCell<ZipArchive<R>>,
+}
+
+#[cfg(feature = "zip")]
+impl<R: Read + Seek> ZipReader<R> {
+    /// Ouvre l'archive lue depuis le lecteur passé en paramètre.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si les données lues ne sont pas au
+    /// format zip.
+    pub fn new(reader: R) -> io::Result<Self> {
+        let archive = ZipArchive::new(reader).map_err(zip_error)?;
+        Ok(Self { archive: RefCell::new(archive) })
+    }
+}
+
+#[cfg(feature = "zip")]
+impl<R: Read + Seek> ResourceReader for ZipReader<R> {
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        let mut name = Vec::new();
+
+        for component in path.components() {
+            match component {
+                Component::Normal(part) => name.push(part.to_string_lossy()),
+                Component::CurDir => (),
+                _ => {
+                    let message = format!("no such file in archive: {}", path.display());
+                    return Err(io::Error::new(io::ErrorKind::NotFound, message));
+                }
+            }
+        }
+
+        // Le fichier est lu entièrement, l'archive ne pouvant lire qu'un seul
+        // fichier à la fois.
+        let mut archive = self.archive.borrow_mut();
+        let mut file = archive.by_name(&name.join("/")).map_err(zip_error)?;
+        let mut data = Vec::with_capacity(usize::try_from(file.size()).unwrap_or(0));
+        file.read_to_end(&mut data)?;
+
+        Ok(Box::new(Cursor::new(data)))
+    }
+}
+
+/// Convertit une erreur de lecture d'archive.
+#[cfg(feature = "zip")]
+fn zip_error(error: ZipError) -> io::Error {
+    match error {
+        ZipError::Io(error) => error,
+        ZipError::FileNotFound => io::Error::new(io::ErrorKind::NotFound, error),
+        error => io::Error::new(io::ErrorKind::InvalidData, error),
+    }
+}
+
+impl<R: ResourceReader + ?Sized> ResourceReader for &R {
+    #[inline]
+    fn open(&self, path: &Path) -> io::Result<Box<dyn Read + '_>> {
+        (**self).open(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{LoadOptions, Map, TilesOrigin};
+    use crate::error::TMXError;
+
+    fn files() -> HashMap<PathBuf, Vec<u8>> {
+        let mut files = HashMap::new();
+        let mut add = |path: &str, content: &[u8]| {
+            files.insert(PathBuf::from(path), content.to_vec());
+        };
+
+        add("maps/level.tmx", br#"
+        <map orientation="orthogonal" width="2" height="1" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" source="../tilesets/terrain.tsx"/>
+            <layer id="1" name="Ground" width="2" height="1">
+                <data encoding="csv">1,2</data>
+            </layer>
+            <objectgroup id="2" name="Items">
+                <object id="1" template="./chest.tx" x="8" y="0"/>
+            </objectgroup>
+        </map>"#);
+        add("maps/chest.tx", br#"
+        <template>
+            <tileset firstgid="1" source="../tilesets/terrain.tsx"/>
+            <object gid="2" width="8" height="8"/>
+        </template>"#);
+        add("tilesets/terrain.tsx", br#"
+        <tileset name="terrain" tilewidth="8" tileheight="8" tilecount="2" columns="2">
+            <image source="terrain.png" width="16" height="8"/>
+        </tileset>"#);
+        add("tilesets/terrain.png", b"png");
+
+        files
+    }
+
+    #[test]
+    fn test_load_tmx_from_memory() {
+        let files = files();
+        let options = LoadOptions { resources: Some(&files), ..LoadOptions::default() };
+        let (map, warnings) = Map::load_tmx_file("maps/level.tmx", &options).unwrap();
+
+        assert!(warnings.is_empty(), "{:?}", warnings);
+
+        assert_eq!(map.base_dir, Some(PathBuf::from("maps")));
+        assert_eq!(map.tilesets()[0].path, Some(PathBuf::from("tilesets/terrain.tsx")));
+        assert_eq!(map.object_groups[0].objects[0].gid, 2);
+
+        match &map.tilesets()[0].origin {
+            Some(TilesOrigin::Image(image)) => {
+                assert_eq!(image.path, Some(PathBuf::from("tilesets/terrain.png")));
+                assert_eq!(image.read(&files).unwrap(), b"png");
+            }
+            origin => panic!("unexpected origin: {:?}", origin),
+        }
+
+        let src = r#"<map orientation="orthogonal" width="1" height="1" tilewidth="8">
+            <tileset firstgid="1" source="tilesets/terrain.tsx"/>
+        </map>"#;
+        let (map, _) = Map::load_tmx_with(src.as_bytes(), &options).unwrap();
+
+        assert_eq!(map.base_dir, None);
+        assert_eq!(map.tilesets()[0].name, "terrain");
+        assert_eq!(map.tilesets()[0].path, Some(PathBuf::from("tilesets/terrain.tsx")));
+
+        let mut missing = files.clone();
+        missing.remove(Path::new("maps/chest.tx"));
+
+        let options = LoadOptions { resources: Some(&missing), ..options };
+        match Map::load_tmx_file("maps/level.tmx", &options).unwrap_err() {
+            TMXError::MissingResource { source, error, .. } => {
+                assert_eq!(source, "./chest.tx");
+                assert_eq!(error.kind(), io::ErrorKind::NotFound);
+            }
+            error => panic!("unexpected error: {}", error),
+        }
+
+        assert!(matches!(Map::load_tmx_file("foo.tmx", &options), Err(TMXError::Io(_))));
+    }
+
+    #[test]
+    fn test_load_tmj_from_memory() {
+        let mut files = files();
+        files.insert(PathBuf::from("maps/level.tmj"), br#"{
+            "orientation": "orthogonal",
+            "width": 2,
+            "height": 1,
+            "tilewidth": 8,
+            "tileheight": 8,
+            "tilesets": [
+                { "firstgid": 3, "source": "../tilesets/terrain.tsj" },
+                {
+                    "firstgid": 5,
+                    "name": "hero",
+                    "tilecount": 1,
+                    "tiles": [{ "id": 0, "image": "hero.png", "imagewidth": 8 }]
+                }
+            ],
+            "layers": [{ "type": "tilelayer", "data": [3, 4] }]
+        }"#.to_vec());
+        files.insert(PathBuf::from("tilesets/terrain.tsj"), br#"{
+            "name": "terrain",
+            "tilewidth": 8,
+            "tileheight": 8,
+            "tilecount": 2,
+            "columns": 2,
+            "image": "terrain.png",
+            "imagewidth": 16,
+            "imageheight": 8
+        }"#.to_vec());
+
+        let options = LoadOptions { resources: Some(&files), ..LoadOptions::default() };
+        let map = Map::load_tmj_file("maps/level.tmj", &options).unwrap();
+        let tileset = &map.tilesets()[0];
+
+        assert_eq!(map.base_dir, Some(PathBuf::from("maps")));
+        assert_eq!(tileset.firstgid, 3);
+        assert_eq!(tileset.name, "terrain");
+        assert_eq!(tileset.source.as_deref(), Some("../tilesets/terrain.tsj"));
+        assert_eq!(tileset.path, Some(PathBuf::from("tilesets/terrain.tsj")));
+
+        match (&tileset.origin, &map.tilesets()[1].origin) {
+            (Some(TilesOrigin::Image(image)), Some(TilesOrigin::Collection(tiles))) => {
+                assert_eq!(image.path, Some(PathBuf::from("tilesets/terrain.png")));
+                assert_eq!(tiles[&0].image.path, Some(PathBuf::from("maps/hero.png")));
+            }
+            origins => panic!("unexpected origins: {:?}", origins),
+        }
+
+        let tmj = String::from_utf8(files[Path::new("maps/level.tmj")].clone()).unwrap();
+        let tsx = tmj.replace("terrain.tsj", "terrain.tsx");
+        files.insert(PathBuf::from("maps/tsx.tmj"), tsx.into_bytes());
+
+        let options = LoadOptions { resources: Some(&files), ..LoadOptions::default() };
+        let map = Map::load_tmj_file("maps/tsx.tmj", &options).unwrap();
+        let tileset = &map.tilesets()[0];
+
+        assert_eq!(tileset.firstgid, 3);
+        assert_eq!(tileset.name, "terrain");
+        assert_eq!(tileset.path, Some(PathBuf::from("tilesets/terrain.tsx")));
+
+        match &tileset.origin {
+            Some(TilesOrigin::Image(image)) => {
+                assert_eq!(image.path, Some(PathBuf::from("tilesets/terrain.png")));
+            }
+            origin => panic!("unexpected origin: {:?}", origin),
+        }
+
+        files.remove(Path::new("tilesets/terrain.tsj"));
+        let options = LoadOptions { resources: Some(&files), ..LoadOptions::default() };
+        let error = Map::load_tmj_file("maps/level.tmj", &options).unwrap_err();
+        assert!(error.to_string().contains("../tilesets/terrain.tsj"), "{}", error);
+    }
+
+    #[cfg(feature = "zip")]
+    #[test]
+    fn test_load_tmx_from_zip() {
+        use std::io::Write;
+
+        use zip::ZipWriter;
+        use zip::write::FileOptions;
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+
+        for (path, content) in files() {
+            writer.start_file(path.to_str().unwrap(), FileOptions::default()).unwrap();
+            writer.write_all(&content).unwrap();
+        }
+
+        let archive = writer.finish().unwrap();
+        let reader = ZipReader::new(Cursor::new(archive.into_inner())).unwrap();
+        let options = LoadOptions { resources: Some(&reader), ..LoadOptions::default() };
+        let (map, _) = Map::load_tmx_file("./maps/level.tmx", &options).unwrap();
+
+        assert_eq!(map.tilesets()[0].name, "terrain");
+        assert_eq!(map.object_groups[0].objects[0].gid, 2);
+
+        match &map.tilesets()[0].origin {
+            Some(TilesOrigin::Image(image)) => assert_eq!(image.read(&reader).unwrap(), b"png"),
+            origin => panic!("unexpected origin: {:?}", origin),
+        }
+
+        let error = reader.open(Path::new("maps/../../level.tmx")).err().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(reader.open(Path::new("maps/missing.tmx")).is_err());
+    }
+}
@@ -0,0 +1,596 @@
+use std::io::{self, Write};
+
+use flate2::write::{GzEncoder, ZlibEncoder};
+use quick_xml::Writer;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use serde::{Serialize, Serializer};
+
+use crate::data::{Color, Compression, Encoding, EnumValue, Image, Map, Object, ObjectGroup,
+                  Orientation, Properties, PropertyValue, StaggerAxis, TileSet, TilesOrigin};
+use crate::error::TMXError;
+
+impl Serialize for Color {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer
+    {
+        serializer.collect_str(self)
+    }
+}
+
+/// Liste d'attributs d'une balise, sous la forme de couples clef-valeur.
+type Attributes<'a> = [(&'a str, String)];
+
+/// Surcouche de [`Writer`] facilitant l'écriture des balises d'un fichier `.tmx`.
+struct TMXWriter<W: Write> {
+    writer: Writer<W>,
+}
+
+impl<W: Write> TMXWriter<W> {
+    /// Crée un nouvel écrivain indentant chaque balise d'un espace.
+    fn new(writer: W) -> Self {
+        Self { writer: Writer::new_with_indent(writer, b' ', 1) }
+    }
+
+    /// Écrit une balise ouvrante possédant les attributs passés en paramètre.
+    fn start(&mut self, name: &str, attributes: &Attributes) -> Result<(), TMXError> {
+        self.writer.write_event(Event::Start(tag(name, attributes)))?;
+        Ok(())
+    }
+
+    /// Écrit la balise fermante associée au nom passé en paramètre.
+    fn end(&mut self, name: &str) -> Result<(), TMXError> {
+        self.writer.write_event(Event::End(BytesEnd::borrowed(name.as_bytes())))?;
+        Ok(())
+    }
+
+    /// Écrit une balise vide possédant les attributs passés en paramètre.
+    fn empty(&mut self, name: &str, attributes: &Attributes) -> Result<(), TMXError> {
+        self.writer.write_event(Event::Empty(tag(name, attributes)))?;
+        Ok(())
+    }
+
+    /// Écrit une balise dont le contenu est le texte passé en paramètre.
+    fn text(&mut self, name: &str, attributes: &Attributes, text: &str) -> Result<(), TMXError> {
+        self.start(name, attributes)?;
+        self.writer.write_event(Event::Text(BytesText::from_plain_str(text)))?;
+        self.end(name)
+    }
+}
+
+/// Crée une balise possédant les attributs passés en paramètre.
+fn tag<'a>(name: &'a str, attributes: &'a Attributes) -> BytesStart<'a> {
+    let attributes = attributes.iter().map(|(key, value)| (*key, value.as_str()));
+    BytesStart::borrowed_name(name.as_bytes()).with_attributes(attributes)
+}
+
+/// Écrit la map au format `.tmx`, en encodant son calque de tuiles avec
+/// l'encodage passé en paramètre.
+pub(crate) fn write_map<W: Write>(
+    map: &Map,
+    writer: W,
+    encoding: Encoding,
+) -> Result<(), TMXError> {
+    let mut writer = TMXWriter::new(writer);
+
+    let decl = BytesDecl::new(b"1.0", Some(b"UTF-8"), None);
+    writer.writer.write_event(Event::Decl(decl))?;
+
+    let mut attributes = vec![
+        ("version", String::from("1.10")),
+        ("orientation", map.orientation.to_string()),
+        ("renderorder", String::from("right-down")),
+        ("width", map.size.x.to_string()),
+        ("height", map.size.y.to_string()),
+        ("tilewidth", map.tile_size.x.to_string()),
+        ("tileheight", map.tile_size.y.to_string()),
+        ("infinite", String::from("0")),
+    ];
+    let (next_layer_id, next_object_id) = map.next_ids();
+
+    if map.orientation == Orientation::Hexagonal {
+        attributes.push(("hexsidelength", map.hex_side_length.to_string()));
+    }
+
+    if map.stagger_axis != StaggerAxis::None {
+        attributes.push(("staggeraxis", map.stagger_axis.to_string()));
+        attributes.push(("staggerindex", map.stagger_index.to_string()));
+    }
+
+    attributes.push(("nextlayerid", next_layer_id.to_string()));
+    attributes.push(("nextobjectid", next_object_id.to_string()));
+
+    writer.start("map", &attributes)?;
+    write_properties(&mut writer, &map.properties)?;
+
+    for tileset in map.tilesets() {
+        write_tileset(&mut writer, tileset)?;
+    }
+
+    write_layer(&mut writer, map, encoding)?;
+
+    for group in &map.object_groups {
+        write_object_group(&mut writer, group)?;
+    }
+
+    writer.end("map")
+}
+
+/// Écrit un jeu de tuiles, ou seulement sa référence s'il est externe à la map.
+fn write_tileset<W: Write>(writer: &mut TMXWriter<W>, tileset: &TileSet) -> Result<(), TMXError> {
+    if let Some(source) = &tileset.source {
+        let attributes = [
+            ("firstgid", tileset.firstgid.to_string()),
+            ("source", source.clone()),
+        ];
+
+        return writer.empty("tileset", &attributes);
+    }
+
+    let mut attributes = vec![
+        ("firstgid", tileset.firstgid.to_string()),
+        ("name", tileset.name.clone()),
+        ("tilewidth", tileset.size.x.to_string()),
+        ("tileheight", tileset.size.y.to_string()),
+    ];
+
+    if tileset.spacing != 0 {
+        attributes.push(("spacing", tileset.spacing.to_string()));
+    }
+
+    if tileset.margin != 0 {
+        attributes.push(("margin", tileset.margin.to_string()));
+    }
+
+    attributes.push(("tilecount", tileset.count.to_string()));
+    attributes.push(("columns", tileset.columns.to_string()));
+
+    writer.start("tileset", &attributes)?;
+    write_properties(writer, &tileset.properties)?;
+
+    let mut ids: Vec<u16> = tileset.tiles_properties.keys().copied().collect();
+
+    match &tileset.origin {
+        Some(TilesOrigin::Image(image)) => write_image(writer, image)?,
+        Some(TilesOrigin::Collection(tiles)) => ids.extend(tiles.keys()),
+        None => (),
+    }
+
+    ids.sort_unstable();
+    ids.dedup();
+
+    for id in ids {
+        let image = match &tileset.origin {
+            Some(TilesOrigin::Collection(tiles)) => tiles.get(&id).map(|tile| &tile.image),
+            _ => None,
+        };
+
+        writer.start("tile", &[("id", id.to_string())])?;
+
+        if let Some(properties) = tileset.tile_properties(id) {
+            write_properties(writer, properties)?;
+        }
+
+        if let Some(image) = image {
+            write_image(writer, image)?;
+        }
+
+        writer.end("tile")?;
+    }
+
+    writer.end("tileset")
+}
+
+/// Écrit une image, ainsi que son contenu si celle-ci est intégrée au fichier.
+fn write_image<W: Write>(writer: &mut TMXWriter<W>, image: &Image) -> Result<(), TMXError> {
+    let mut attributes = Vec::new();
+
+    if let Some(format) = &image.format {
+        attributes.push(("format", format.clone()));
+    }
+
+    if !image.source.is_empty() {
+        attributes.push(("source", image.source.clone()));
+    }
+
+    if let Some(trans) = image.trans {
+        attributes.push(("trans", trans.to_string().trim_start_matches('#').to_string()));
+    }
+
+    attributes.push(("width", image.size.x.to_string()));
+    attributes.push(("height", image.size.y.to_string()));
+
+    match &image.data {
+        Some(data) => {
+            writer.start("image", &attributes)?;
+            writer.text("data", &[("encoding", String::from("base64"))], &base64::encode(data))?;
+            writer.end("image")
+        }
+        None => writer.empty("image", &attributes),
+    }
+}
+
+/// Écrit le calque de tuiles de la map avec l'encodage passé en paramètre.
+fn write_layer<W: Write>(
+    writer: &mut TMXWriter<W>,
+    map: &Map,
+    encoding: Encoding,
+) -> Result<(), TMXError> {
+    let attributes = [
+        ("id", map.tile_layer_id().to_string()),
+        ("width", map.size.x.to_string()),
+        ("height", map.size.y.to_string()),
+    ];
+
+    writer.start("layer", &attributes)?;
+
+    match encoding {
+        Encoding::Csv => {
+            let attributes = [("encoding", String::from("csv"))];
+            writer.text("data", &attributes, &encode_csv_data(map))?;
+        }
+        Encoding::Base64(compression) => {
+            let mut attributes = vec![("encoding", String::from("base64"))];
+
+            if let Some(compression) = compression {
+                attributes.push(("compression", compression.to_string()));
+            }
+
+            let data = encode_base64_data(&map.tiles, compression)?;
+            writer.text("data", &attributes, &data)?;
+        }
+    }
+
+    writer.end("layer")
+}
+
+/// Écrit un groupe d'objets ainsi que tous ses objets.
+fn write_object_group<W: Write>(
+    writer: &mut TMXWriter<W>,
+    group: &ObjectGroup,
+) -> Result<(), TMXError> {
+    let attributes = [
+        ("id", group.id.to_string()),
+        ("name", group.name.clone()),
+    ];
+
+    writer.start("objectgroup", &attributes)?;
+    write_properties(writer, &group.properties)?;
+
+    for object in &group.objects {
+        write_object(writer, object)?;
+    }
+
+    writer.end("objectgroup")
+}
+
+/// Écrit un objet, en omettant les attributs possédant leur valeur par défaut.
+fn write_object<W: Write>(writer: &mut TMXWriter<W>, object: &Object) -> Result<(), TMXError> {
+    let mut attributes = vec![("id", object.id.to_string())];
+
+    if let Some(template) = &object.template {
+        attributes.push(("template", template.clone()));
+    }
+
+    if let Some(gid) = object.valid_gid() {
+        attributes.push(("gid", gid.to_string()));
+    }
+
+    attributes.push(("x", object.coords.x.to_string()));
+    attributes.push(("y", object.coords.y.to_string()));
+
+    if object.size.x != 0 {
+        attributes.push(("width", object.size.x.to_string()));
+    }
+
+    if object.size.y != 0 {
+        attributes.push(("height", object.size.y.to_string()));
+    }
+
+    if object.properties.is_empty() {
+        writer.empty("object", &attributes)
+    } else {
+        writer.start("object", &attributes)?;
+        write_properties(writer, &object.properties)?;
+        writer.end("object")
+    }
+}
+
+/// Écrit une liste de propriétés personnalisées, si celle-ci n'est pas vide.
+fn write_properties<W: Write>(
+    writer: &mut TMXWriter<W>,
+    properties: &Properties,
+) -> Result<(), TMXError> {
+    if properties.is_empty() {
+        return Ok(());
+    }
+
+    writer.start("properties", &[])?;
+
+    for (name, value) in properties {
+        let mut attributes = vec![("name", name.clone())];
+
+        if value.type_name() != "string" {
+            attributes.push(("type", String::from(value.type_name())));
+        }
+
+        if let PropertyValue::Enum { property_type, .. } = value {
+            attributes.push(("propertytype", property_type.clone()));
+        }
+
+        match value {
+            PropertyValue::Class { property_type, properties } => {
+                attributes.push(("propertytype", property_type.clone()));
+
+                writer.start("property", &attributes)?;
+                write_properties(writer, properties)?;
+                writer.end("property")?;
+            }
+            // Les chaînes de caractères sur plusieurs lignes sont écrites en tant que
+            // contenu de la balise, les retours à la ligne n'étant pas conservés dans
+            // les attributs.
+            PropertyValue::String(s) if s.contains('\n') => {
+                writer.text("property", &attributes, s)?
+            }
+            _ => {
+                attributes.push(("value", property_value(value)));
+                writer.empty("property", &attributes)?;
+            }
+        }
+    }
+
+    writer.end("properties")
+}
+
+/// Convertit la valeur d'une propriété en chaîne de caractères.
+fn property_value(value: &PropertyValue) -> String {
+    match value {
+        PropertyValue::Bool(b) => b.to_string(),
+        PropertyValue::Color(color) => color.to_string(),
+        PropertyValue::Float(f) => f.to_string(),
+        PropertyValue::File(s) | PropertyValue::String(s) => s.clone(),
+        PropertyValue::Int(i) => i.to_string(),
+        PropertyValue::Object(id) => id.to_string(),
+        PropertyValue::Class { .. } => String::new(),
+        PropertyValue::Enum { value: EnumValue::String(s), .. } => s.clone(),
+        PropertyValue::Enum { value: EnumValue::Int(i), .. } => i.to_string(),
+    }
+}
+
+/// Encode les tuiles de la map au format csv, une ligne de la map par ligne de
+/// texte.
+fn encode_csv_data(map: &Map) -> String {
+    let rows: Vec<String> = map.tiles
+        .chunks(usize::from(map.size.x).max(1))
+        .map(|row| row.iter().map(u16::to_string).collect::<Vec<_>>().join(","))
+        .collect();
+
+    format!("\n{}\n", rows.join(",\n"))
+}
+
+/// Encode une liste d'identifiants globaux en base64, chacun étant stocké sur 4
+/// octets au format petit-boutiste puis éventuellement compressé.
+pub(crate) fn encode_base64_data(
+    tiles: &[u16],
+    compression: Option<Compression>,
+) -> io::Result<String> {
+    let data: Vec<u8> = tiles.iter().flat_map(|&gid| u32::from(gid).to_le_bytes()).collect();
+
+    let data = match compression {
+        None => data,
+        Some(Compression::Zlib) => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&data)?;
+            encoder.finish()?
+        }
+        Some(Compression::Gzip) => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&data)?;
+            encoder.finish()?
+        }
+        #[cfg(feature = "zstd")]
+        Some(Compression::Zstd) => zstd::encode_all(data.as_slice(), 0)?,
+        #[cfg(not(feature = "zstd"))]
+        Some(Compression::Zstd) => {
+            let message = "zstd compression requires the `zstd` feature";
+            return Err(io::Error::new(io::ErrorKind::Unsupported, message));
+        }
+    };
+
+    Ok(base64::encode(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use mint::Vector2;
+
+    use crate::data::{Color, Compression, Encoding, EnumValue, Image, Map, Object, ObjectGroup,
+                      Orientation, Properties, PropertyValue, StaggerAxis, StaggerIndex, Tile,
+                      TileSet, TilesOrigin};
+
+    /// Crée une map utilisant chaque élément du modèle.
+    fn full_map() -> Map {
+        let mut properties = Properties::new();
+        properties.insert("bool".to_string(), PropertyValue::Bool(true));
+        properties.insert("color".to_string(), PropertyValue::Color(Color::rgba(1, 2, 3, 4)));
+        properties.insert("file".to_string(), PropertyValue::File("foo.txt".to_string()));
+        properties.insert("float".to_string(), PropertyValue::Float(1.5));
+        properties.insert("int".to_string(), PropertyValue::Int(-3));
+        properties.insert("object".to_string(), PropertyValue::Object(2));
+        properties.insert("string".to_string(), PropertyValue::String("a \"b\" <c>".to_string()));
+        properties.insert("lines".to_string(), PropertyValue::String("a\nb".to_string()));
+        properties.insert("enum".to_string(), PropertyValue::Enum {
+            property_type: "Dir".to_string(),
+            value: EnumValue::String("N".to_string()),
+        });
+        properties.insert("flags".to_string(), PropertyValue::Enum {
+            property_type: "Flags".to_string(),
+            value: EnumValue::Int(3),
+        });
+        properties.insert("class".to_string(), PropertyValue::Class {
+            property_type: "Foo".to_string(),
+            properties: properties.clone(),
+        });
+
+        let mut image = Image::new("", Vector2 { x: 48, y: 24 });
+        image.trans = Some(Color::rgb(255, 0, 255));
+        image.format = Some("png".to_string());
+        image.data = Some(vec![0, 1, 2, 3, 255]);
+
+        let mut tiles_properties = BTreeMap::new();
+        tiles_properties.insert(1, properties.clone());
+
+        let mut map = Map {
+            size: Vector2 { x: 3, y: 2 },
+            tile_size: Vector2 { x: 24, y: 12 },
+            tiles: vec![0, 1, 2, 3, 9, 10],
+            object_groups: vec![
+                ObjectGroup {
+                    id: 2,
+                    name: "foo".to_string(),
+                    objects: vec![
+                        Object {
+                            id: 1,
+                            gid: 3,
+                            coords: [10, 20].into(),
+                            size: [24, 12].into(),
+                            properties: properties.clone(),
+                            template: None,
+                        },
+                        Object {
+                            id: 2,
+                            coords: [5, 6].into(),
+                            ..Default::default()
+                        },
+                    ],
+                    properties: properties.clone(),
+                },
+                ObjectGroup {
+                    id: 3,
+                    name: "bar".to_string(),
+                    ..Default::default()
+                },
+            ],
+            orientation: Orientation::Hexagonal,
+            stagger_axis: StaggerAxis::XAxis,
+            stagger_index: StaggerIndex::Even,
+            hex_side_length: 6,
+            properties: properties.clone(),
+            ..Default::default()
+        };
+
+        map.add_tilesets(vec![
+            TileSet {
+                firstgid: 1,
+                size: Vector2 { x: 24, y: 12 },
+                count: 4,
+                columns: 2,
+                spacing: 1,
+                margin: 2,
+                name: "image".to_string(),
+                origin: Some(TilesOrigin::Image(image.clone())),
+                properties,
+                tiles_properties,
+                ..Default::default()
+            },
+            TileSet {
+                firstgid: 5,
+                source: Some("external.tsx".to_string()),
+                ..Default::default()
+            },
+            TileSet {
+                firstgid: 9,
+                size: Vector2 { x: 24, y: 12 },
+                count: 2,
+                name: "collection".to_string(),
+                origin: Some(TilesOrigin::new_collection_from(vec![
+                    Tile::new(0, Image::new("a.png", Vector2 { x: 24, y: 12 })),
+                    Tile::new(3, image),
+                ])),
+                ..Default::default()
+            },
+        ]);
+
+        map
+    }
+
+    /// Écrit la map avec l'encodage spécifié, puis vérifie que son chargement
+    /// produit la même map.
+    fn assert_round_trip(map: &Map, encoding: Encoding) {
+        let mut tmx = Vec::new();
+        map.write_tmx(&mut tmx, encoding).unwrap();
+
+        assert_eq!(&Map::load_tmx(&tmx).unwrap(), map, "{}", String::from_utf8_lossy(&tmx));
+    }
+
+    #[test]
+    fn test_write_tmx_round_trip() {
+        let map = full_map();
+
+        assert_round_trip(&map, Encoding::Csv);
+        assert_round_trip(&map, Encoding::Base64(None));
+        assert_round_trip(&map, Encoding::Base64(Some(Compression::Zlib)));
+        assert_round_trip(&map, Encoding::Base64(Some(Compression::Gzip)));
+
+        #[cfg(feature = "zstd")]
+        assert_round_trip(&map, Encoding::Base64(Some(Compression::Zstd)));
+    }
+
+    #[test]
+    fn test_write_tmx_csv() {
+        let map = Map {
+            size: Vector2 { x: 3, y: 2 },
+            tile_size: Vector2 { x: 24, y: 24 },
+            tiles: vec![0, 0, 0, 3, 2, 1],
+            ..Default::default()
+        };
+
+        let mut tmx = Vec::new();
+        map.write_tmx(&mut tmx, Encoding::Csv).unwrap();
+
+        let should_be = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="3" height="2" tilewidth="24" tileheight="24" infinite="0" nextlayerid="2" nextobjectid="1">
+ <layer id="1" width="3" height="2">
+  <data encoding="csv">
+0,0,0,
+3,2,1
+</data>
+ </layer>
+</map>"#;
+
+        assert_eq!(String::from_utf8(tmx).unwrap(), should_be);
+    }
+
+    #[test]
+    fn test_write_tmx_layer_ids() {
+        let group = |id: u16, objects: Vec<Object>| ObjectGroup {
+            id,
+            objects,
+            ..Default::default()
+        };
+        let map = Map {
+            object_groups: vec![
+                group(1, vec![Object { id: 5, ..Default::default() }]),
+                group(2, Vec::new()),
+            ],
+            ..Default::default()
+        };
+
+        let mut tmx = Vec::new();
+        map.write_tmx(&mut tmx, Encoding::Csv).unwrap();
+        let tmx = String::from_utf8(tmx).unwrap();
+
+        assert!(tmx.contains(r#"nextlayerid="4" nextobjectid="6">"#), "{}", tmx);
+        assert!(tmx.contains(r#"<layer id="3""#), "{}", tmx);
+    }
+
+    #[cfg(not(feature = "zstd"))]
+    #[test]
+    fn test_write_tmx_unsupported_compression() {
+        let encoding = Encoding::Base64(Some(Compression::Zstd));
+        let result = Map::default().write_tmx(Vec::new(), encoding);
+        assert!(result.is_err());
+    }
+}
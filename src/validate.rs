@@ -0,0 +1,258 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt;
+
+use mint::Vector2;
+
+use crate::data::{EMPTY_TILE, Map, TileSet, TileSetOverlap, TilesOrigin};
+
+/// Incohérence relevée par [`Map::validate`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MapIssue {
+    /// Le nombre de tuiles du calque ne correspond pas à la taille de la map.
+    LayerLength {
+        /// Nombre de tuiles attendu, soit la largeur multipliée par la hauteur.
+        expected: usize,
+        /// Nombre de tuiles du calque.
+        actual: usize,
+    },
+    /// Une tuile du calque n'appartient à aucun jeu de tuiles. Chaque gid
+    /// invalide n'est signalé qu'une fois.
+    TileGid {
+        /// Identifiant global invalide.
+        gid: u16,
+        /// Indice de la première tuile du calque possédant ce gid.
+        tile: usize,
+    },
+    /// La tuile d'un objet n'appartient à aucun jeu de tuiles.
+    ObjectGid {
+        /// Identifiant de l'objet.
+        object: u16,
+        /// Identifiant global invalide.
+        gid: u16,
+    },
+    /// Les plages d'identifiants globaux de deux jeux de tuiles se chevauchent.
+    TileSetOverlap(TileSetOverlap),
+    /// Un jeu de tuiles découpé dans une image ne possède aucune colonne.
+    ZeroColumns {
+        /// `firstgid` du jeu de tuiles.
+        firstgid: u16,
+    },
+    /// L'image d'un jeu de tuiles ne correspond pas à sa taille de tuile, son
+    /// espacement, sa marge et son nombre de tuiles.
+    ImageSize {
+        /// `firstgid` du jeu de tuiles.
+        firstgid: u16,
+        /// Taille de l'image en pixels.
+        image: Vector2<u16>,
+        /// Nombre de colonnes et de lignes de tuiles contenues dans l'image.
+        grid: Vector2<u16>,
+        /// Nombre de colonnes et de lignes de tuiles attendues d'après le jeu.
+        expected: Vector2<u16>,
+    },
+    /// Plusieurs objets de la map partagent le même identifiant.
+    DuplicateObjectId {
+        /// Identifiant partagé.
+        id: u16,
+        /// Nombre d'objets le possédant.
+        count: usize,
+    },
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapIssue::LayerLength { expected, actual } => {
+                write!(f, "the layer has {} tiles instead of {}", actual, expected)
+            }
+            MapIssue::TileGid { gid, tile } => {
+                write!(f, "gid {} of tile {} does not belong to any tileset", gid, tile)
+            }
+            MapIssue::ObjectGid { object, gid } => {
+                write!(f, "gid {} of object {} does not belong to any tileset", gid, object)
+            }
+            MapIssue::TileSetOverlap(overlap) => write!(f, "{}", overlap),
+            MapIssue::ZeroColumns { firstgid } => {
+                write!(f, "the tileset with firstgid {} has no columns", firstgid)
+            }
+            MapIssue::ImageSize { firstgid, image, grid, expected } => write!(
+                f,
+                "the image of the tileset with firstgid {} ({}x{} pixels) holds {}x{} tiles \
+                 instead of {}x{}",
+                firstgid, image.x, image.y, grid.x, grid.y, expected.x, expected.y,
+            ),
+            MapIssue::DuplicateObjectId { id, count } => {
+                write!(f, "{} objects share the id {}", count, id)
+            }
+        }
+    }
+}
+
+impl Map {
+    /// Vérifie la cohérence de la map et renvoie la liste des problèmes relevés,
+    /// vide si la map est valide.
+    ///
+    /// Les identifiants globaux suivant un jeu de tuiles externe non chargé sont
+    /// considérés comme lui appartenant, son nombre de tuiles n'étant pas connu.
+    /// Les objets dont l'identifiant vaut 0 ne sont pas vérifiés, celui-ci
+    /// n'étant jamais attribué par Tiled.
+    pub fn validate(&self) -> Vec<MapIssue> {
+        let mut issues = Vec::new();
+
+        let expected = usize::from(self.size.x) * usize::from(self.size.y);
+
+        if self.tiles.len() != expected {
+            issues.push(MapIssue::LayerLength { expected, actual: self.tiles.len() });
+        }
+
+        let mut invalid = BTreeSet::new();
+
+        for (tile, &gid) in self.tiles.iter().enumerate() {
+            if !contains_gid(&self.tilesets, gid) && invalid.insert(gid) {
+                issues.push(MapIssue::TileGid { gid, tile });
+            }
+        }
+
+        let objects = self.object_groups.iter().flat_map(|group| &group.objects);
+        let mut ids = BTreeMap::new();
+
+        for object in objects {
+            if let Some(gid) = object.valid_gid() {
+                if !contains_gid(&self.tilesets, gid) {
+                    issues.push(MapIssue::ObjectGid { object: object.id, gid });
+                }
+            }
+
+            if object.id != 0 {
+                *ids.entry(object.id).or_insert(0) += 1;
+            }
+        }
+
+        for (id, count) in ids.into_iter().filter(|&(_, count)| count > 1) {
+            issues.push(MapIssue::DuplicateObjectId { id, count });
+        }
+
+        issues.extend(self.tileset_overlaps().into_iter().map(MapIssue::TileSetOverlap));
+
+        for tileset in &self.tilesets {
+            if let Some(TilesOrigin::Image(image)) = &tileset.origin {
+                if tileset.columns == 0 {
+                    issues.push(MapIssue::ZeroColumns { firstgid: tileset.firstgid });
+                } else if let Some(issue) = check_image_size(tileset, image.size) {
+                    issues.push(issue);
+                }
+            }
+        }
+
+        issues
+    }
+}
+
+/// Indique si le gid passé en paramètre appartient à l'un des jeux de tuiles,
+/// triés par `firstgid`.
+fn contains_gid(tilesets: &[TileSet], gid: u16) -> bool {
+    gid == EMPTY_TILE || Map::gid_owner(tilesets, gid).is_some()
+}
+
+/// Vérifie que l'image d'un jeu de tuiles contient exactement ses colonnes, et
+/// suffisamment de lignes pour toutes ses tuiles.
+fn check_image_size(tileset: &TileSet, image: Vector2<u16>) -> Option<MapIssue> {
+    // Nombre de tuiles contenues sur une dimension de l'image, comme calculé par
+    // Tiled : la marge entoure l'image et l'espacement sépare les tuiles.
+    let fit = |length: u16, tile: u16| {
+        let length = u32::from(length).saturating_sub(2 * u32::from(tileset.margin));
+        let step = u32::from(tile) + u32::from(tileset.spacing);
+
+        match step {
+            0 => 0,
+            _ => (length + u32::from(tileset.spacing)) / step,
+        }
+    };
+
+    let grid = Vector2 {
+        x: fit(image.x, tileset.size.x),
+        y: fit(image.y, tileset.size.y),
+    };
+
+    let columns = u32::from(tileset.columns);
+    let rows = (u32::from(tileset.count) + columns - 1) / columns;
+
+    if grid.x == columns && grid.y >= rows {
+        return None;
+    }
+
+    let saturate = |value: u32| u16::try_from(value).unwrap_or(u16::MAX);
+
+    Some(MapIssue::ImageSize {
+        firstgid: tileset.firstgid,
+        image,
+        grid: Vector2 { x: saturate(grid.x), y: saturate(grid.y) },
+        expected: Vector2 { x: tileset.columns, y: saturate(rows) },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::{Image, Object, ObjectGroup};
+
+    #[test]
+    fn test_validate_map() {
+        let src = r#"<map orientation="orthogonal" width="3" height="2" tilewidth="8" tileheight="8">
+            <tileset firstgid="1" name="terrain" tilewidth="8" tileheight="8" spacing="1" margin="2"
+                     tilecount="6" columns="3">
+                <image source="terrain.png" width="30" height="21"/>
+            </tileset>
+            <tileset firstgid="7" source="external.tsx"/>
+            <layer id="1" name="Ground" width="3" height="2">
+                <data encoding="csv">1,2,3,4,6,9</data>
+            </layer>
+        </map>"#;
+
+        let mut map = Map::load_tmx(src).unwrap();
+        assert_eq!(map.validate(), vec![]);
+
+        map.tiles.remove(1);
+        map.add_tileset(TileSet {
+            firstgid: 20,
+            size: Vector2 { x: 8, y: 8 },
+            count: 5,
+            origin: Some(TilesOrigin::Image(Image::new("a.png", Vector2 { x: 16, y: 16 }))),
+            ..Default::default()
+        });
+        map.add_tileset(TileSet {
+            firstgid: 30,
+            size: Vector2 { x: 8, y: 8 },
+            count: 5,
+            columns: 2,
+            origin: Some(TilesOrigin::Image(Image::new("b.png", Vector2 { x: 24, y: 16 }))),
+            ..Default::default()
+        });
+        map.add_tileset(TileSet { firstgid: 33, count: 4, ..Default::default() });
+        map.tilesets_mut()[1].path = Some("external.tsx".into());
+
+        let object = |id, gid| Object { id, gid, ..Default::default() };
+        map.object_groups.push(ObjectGroup {
+            objects: vec![object(1, 0), object(2, 40), object(2, 3), object(0, 0), object(0, 0)],
+            ..Default::default()
+        });
+
+        assert_eq!(map.validate(), vec![
+            MapIssue::LayerLength { expected: 6, actual: 5 },
+            MapIssue::TileGid { gid: 9, tile: 4 },
+            MapIssue::ObjectGid { object: 2, gid: 40 },
+            MapIssue::DuplicateObjectId { id: 2, count: 2 },
+            MapIssue::TileSetOverlap(TileSetOverlap { first: 30, second: 33, gids: 33..=34 }),
+            MapIssue::ZeroColumns { firstgid: 20 },
+            MapIssue::ImageSize {
+                firstgid: 30,
+                image: Vector2 { x: 24, y: 16 },
+                grid: Vector2 { x: 3, y: 2 },
+                expected: Vector2 { x: 2, y: 3 },
+            },
+        ]);
+
+        let message = map.validate()[1].to_string();
+        assert_eq!(message, "gid 9 of tile 4 does not belong to any tileset");
+    }
+}
@@ -0,0 +1,308 @@
+use std::convert::TryFrom;
+
+use mint::Vector2;
+use regex::Regex;
+use serde::Deserialize;
+use serde::de::Error as _;
+
+use crate::json::TMJError;
+
+/// Rectangle exprimé en pixels dans l'espace du monde.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Hash)]
+pub struct Rect {
+    /// Abscisse du coin supérieur gauche.
+    pub x: i32,
+    /// Ordonnée du coin supérieur gauche.
+    pub y: i32,
+    /// Largeur du rectangle.
+    pub width: u32,
+    /// Hauteur du rectangle.
+    pub height: u32,
+}
+
+impl Rect {
+    /// Crée un nouveau rectangle.
+    #[inline]
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    /// Indique si les deux rectangles se chevauchent.
+    ///
+    /// Deux rectangles se touchant seulement par un bord ne se chevauchent pas,
+    /// et un rectangle vide ne chevauche aucun rectangle.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        let (x, y) = (i64::from(self.x), i64::from(self.y));
+        let (other_x, other_y) = (i64::from(other.x), i64::from(other.y));
+
+        self.width != 0 && self.height != 0 && other.width != 0 && other.height != 0
+            && x < other_x + i64::from(other.width) && other_x < x + i64::from(self.width)
+            && y < other_y + i64::from(other.height) && other_y < y + i64::from(self.height)
+    }
+}
+
+/// Contient les données d'un fichier `.world`, décrivant la disposition de
+/// plusieurs maps dans un même monde.
+#[derive(Clone, Debug, Default)]
+pub struct World {
+    /// Maps du monde et leur position.
+    pub maps: Vec<WorldMap>,
+    /// Motifs permettant de positionner les maps d'après le nom de leur fichier.
+    pub patterns: Vec<WorldPattern>,
+    /// Indique si Tiled n'affiche que les maps adjacentes à la map courante.
+    pub only_show_adjacent_maps: bool,
+}
+
+impl World {
+    /// Instancie un nouveau monde depuis les données d'un fichier `.world`.
+    ///
+    /// # Erreurs
+    ///
+    /// Cette fonction retourne une erreur si les données passées en paramètre ne
+    /// sont pas au format JSON, ou si l'expression régulière d'un motif est
+    /// invalide.
+    pub fn load<D: AsRef<[u8]>>(data: D) -> Result<Self, TMJError> {
+        World::try_from(serde_json::from_slice::<JsonWorld>(data.as_ref())?)
+    }
+
+    /// Ajoute au monde les fichiers correspondant à l'un de ses motifs.
+    ///
+    /// Les fichiers déjà présents dans le monde, ainsi que ceux ne correspondant
+    /// à aucun motif, sont ignorés. Le format `.world` ne listant pas les fichiers
+    /// concernés par les motifs, ceux-ci sont généralement les fichiers du
+    /// dossier contenant le monde.
+    pub fn add_files<'a, I>(&mut self, file_names: I)
+    where I: IntoIterator<Item = &'a str>
+    {
+        for file_name in file_names {
+            if self.map(file_name).is_some() {
+                continue;
+            }
+
+            let map = self.patterns.iter().find_map(|pattern| pattern.match_file(file_name));
+            self.maps.extend(map);
+        }
+    }
+
+    /// Renvoie la map du monde associée au fichier passé en paramètre.
+    pub fn map(&self, file_name: &str) -> Option<&WorldMap> {
+        self.maps.iter().find(|map| map.file_name == file_name)
+    }
+
+    /// Renvoie les maps du monde chevauchant le rectangle passé en paramètre.
+    pub fn maps_in(&self, rect: Rect) -> impl Iterator<Item = &WorldMap> {
+        self.maps.iter().filter(move |map| map.rect.intersects(&rect))
+    }
+}
+
+/// Représente une map placée dans un monde.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct WorldMap {
+    /// Chemin d'accès vers le fichier de la map, relatif au fichier du monde.
+    pub file_name: String,
+    /// Position et taille de la map dans le monde.
+    ///
+    /// Tiled n'écrivant pas toujours la taille des maps, celle-ci peut être
+    /// nulle tant qu'elle n'a pas été renseignée à partir de la map chargée.
+    pub rect: Rect,
+}
+
+/// Motif positionnant les maps dont le nom de fichier correspond à une
+/// expression régulière.
+///
+/// Les deux premiers groupes capturés par l'expression donnent les coordonnées
+/// de la map, multipliées par `multiplier` puis décalées de `offset`.
+#[derive(Clone, Debug)]
+pub struct WorldPattern {
+    /// Expression régulière, ancrée afin de correspondre à l'ensemble du nom de
+    /// fichier comme dans Tiled.
+    pub regexp: Regex,
+    /// Facteurs appliqués aux coordonnées capturées.
+    pub multiplier: Vector2<i32>,
+    /// Décalage appliqué après multiplication.
+    pub offset: Vector2<i32>,
+    /// Taille des maps correspondant au motif.
+    pub map_size: Vector2<u32>,
+}
+
+impl WorldPattern {
+    /// Renvoie la map correspondant au nom de fichier passé en paramètre, ou
+    /// `None` si celui-ci ne correspond pas au motif.
+    pub fn match_file(&self, file_name: &str) -> Option<WorldMap> {
+        let captures = self.regexp.captures(file_name)?;
+        let coordinate = |i: usize| captures.get(i)?.as_str().parse::<i32>().ok();
+        let x = coordinate(1)?.checked_mul(self.multiplier.x)?.checked_add(self.offset.x)?;
+        let y = coordinate(2)?.checked_mul(self.multiplier.y)?.checked_add(self.offset.y)?;
+
+        Some(WorldMap {
+            file_name: file_name.to_string(),
+            rect: Rect::new(x, y, self.map_size.x, self.map_size.y),
+        })
+    }
+}
+
+/// Représentation JSON d'un monde.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonWorld {
+    #[serde(default)]
+    maps: Vec<JsonWorldMap>,
+    #[serde(default)]
+    patterns: Vec<JsonWorldPattern>,
+    #[serde(default)]
+    only_show_adjacent_maps: bool,
+}
+
+impl TryFrom<JsonWorld> for World {
+    type Error = TMJError;
+
+    fn try_from(json: JsonWorld) -> Result<Self, Self::Error> {
+        let maps = json.maps
+            .into_iter()
+            .map(|map| WorldMap {
+                file_name: map.file_name,
+                rect: Rect::new(map.x, map.y, map.width, map.height),
+            })
+            .collect();
+
+        let patterns = json.patterns
+            .into_iter()
+            .map(|pattern| {
+                let regexp = Regex::new(&format!("^(?:{})$", pattern.regexp))
+                    .map_err(TMJError::custom)?;
+
+                // Sans taille explicite, les maps occupent tout l'espace séparant deux
+                // positions successives.
+                let map_size = Vector2 {
+                    x: pattern.map_width.unwrap_or(pattern.multiplier_x.unsigned_abs()),
+                    y: pattern.map_height.unwrap_or(pattern.multiplier_y.unsigned_abs()),
+                };
+
+                Ok(WorldPattern {
+                    regexp,
+                    multiplier: [pattern.multiplier_x, pattern.multiplier_y].into(),
+                    offset: [pattern.offset_x, pattern.offset_y].into(),
+                    map_size,
+                })
+            })
+            .collect::<Result<_, TMJError>>()?;
+
+        Ok(World {
+            maps,
+            patterns,
+            only_show_adjacent_maps: json.only_show_adjacent_maps,
+        })
+    }
+}
+
+/// Représentation JSON d'une map placée dans un monde.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonWorldMap {
+    file_name: String,
+    #[serde(default)]
+    x: i32,
+    #[serde(default)]
+    y: i32,
+    #[serde(default)]
+    width: u32,
+    #[serde(default)]
+    height: u32,
+}
+
+/// Représentation JSON d'un motif de monde.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonWorldPattern {
+    regexp: String,
+    #[serde(default)]
+    multiplier_x: i32,
+    #[serde(default)]
+    multiplier_y: i32,
+    #[serde(default)]
+    offset_x: i32,
+    #[serde(default)]
+    offset_y: i32,
+    #[serde(default)]
+    map_width: Option<u32>,
+    #[serde(default)]
+    map_height: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORLD: &str = r#"{
+        "type": "world",
+        "maps": [
+            { "fileName": "town.tmx", "x": -640, "y": 0, "width": 640, "height": 480 },
+            { "fileName": "map-x1-y1.tmx", "x": 0, "y": 0, "width": 100, "height": 100 }
+        ],
+        "patterns": [
+            {
+                "regexp": "map-x0*(\\d+)-y0*(\\d+)\\.tmx",
+                "multiplierX": 320,
+                "multiplierY": 240,
+                "offsetX": -320,
+                "offsetY": -240
+            },
+            {
+                "regexp": "cave(\\d+)_(\\d+)\\.tmx",
+                "multiplierX": 100,
+                "multiplierY": 100,
+                "offsetX": 0,
+                "offsetY": 1000,
+                "mapWidth": 50,
+                "mapHeight": 60
+            }
+        ],
+        "onlyShowAdjacentMaps": false
+    }"#;
+
+    #[test]
+    fn test_rect_intersects() {
+        let rect = Rect::new(0, 0, 10, 10);
+
+        assert!(rect.intersects(&Rect::new(5, 5, 10, 10)));
+        assert!(rect.intersects(&Rect::new(-5, -5, 10, 10)));
+        assert!(rect.intersects(&Rect::new(2, 2, 2, 2)));
+        assert!(!rect.intersects(&Rect::new(10, 0, 10, 10)));
+        assert!(!rect.intersects(&Rect::new(0, -10, 10, 10)));
+        assert!(!rect.intersects(&Rect::new(5, 5, 0, 10)));
+        assert!(Rect::new(i32::MAX, 0, u32::MAX, 1).intersects(&Rect::new(i32::MAX, 0, 1, 1)));
+    }
+
+    #[test]
+    fn test_load_world() {
+        let mut world = World::load(WORLD).unwrap();
+
+        assert_eq!(world.map("town.tmx").unwrap().rect, Rect::new(-640, 0, 640, 480));
+        assert_eq!(world.patterns.len(), 2);
+        assert_eq!(world.patterns[0].map_size, Vector2 { x: 320, y: 240 });
+
+        world.add_files(vec![
+            "map-x1-y1.tmx",
+            "map-x02-y3.tmx",
+            "cave1_2.tmx",
+            "map-x1-y1.tmx.bak",
+            "foo.tmx",
+        ]);
+
+        assert_eq!(world.maps.len(), 4);
+        assert_eq!(world.map("map-x1-y1.tmx").unwrap().rect, Rect::new(0, 0, 100, 100));
+        assert_eq!(world.map("map-x02-y3.tmx").unwrap().rect, Rect::new(320, 480, 320, 240));
+        assert_eq!(world.map("cave1_2.tmx").unwrap().rect, Rect::new(100, 1200, 50, 60));
+        assert_eq!(world.map("map-x1-y1.tmx.bak"), None);
+
+        let names = |rect| {
+            world.maps_in(rect).map(|map| map.file_name.as_str()).collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(Rect::new(-10, 0, 20, 10)), vec!["town.tmx", "map-x1-y1.tmx"]);
+        assert_eq!(names(Rect::new(400, 500, 1, 1)), vec!["map-x02-y3.tmx"]);
+        assert!(names(Rect::new(0, -100, 100, 100)).is_empty());
+
+        assert!(World::load(r#"{ "patterns": [{ "regexp": "(" }] }"#).is_err());
+    }
+}